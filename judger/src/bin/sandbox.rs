@@ -16,13 +16,20 @@ struct Args {
 	/// memory limit(byte)
 	#[arg(short, long)]
 	memory: u64,
+	/// stack limit(byte), defaults to the address space limit
+	#[arg(long)]
+	stack:  Option<u64>,
 
 	/// enable sandbox
 	#[arg(short, long)]
 	sandbox: Option<bool>,
+
+	/// program reads/writes named files in its working directory
+	#[arg(short, long)]
+	file_io: Option<bool>,
 }
 
-fn set_seccomp() {
+fn set_seccomp(file_io: bool) {
 	unsafe {
 		let ctx = seccomp_init(SCMP_ACT_KILL_PROCESS);
 
@@ -81,6 +88,13 @@ fn set_seccomp() {
 		// std::cout use sys_futex
 		seccomp_rule_add(ctx, SCMP_ACT_ALLOW, 202, 0);
 
+		// file io: open(at) and write are allowed above
+		// allow 77: ftruncate, 85: creat (fopen "w" / ofstream)
+		if file_io {
+			seccomp_rule_add(ctx, SCMP_ACT_ALLOW, 77, 0);
+			seccomp_rule_add(ctx, SCMP_ACT_ALLOW, 85, 0);
+		}
+
 		seccomp_load(ctx);
 	}
 }
@@ -94,7 +108,8 @@ fn set_rlimit(args: &Args) {
 	let memory_byte = args.memory + 64 * 1048576;
 	setrlimit(Resource::AS, memory_byte, memory_byte).unwrap();
 	setrlimit(Resource::DATA, memory_byte, memory_byte).unwrap();
-	setrlimit(Resource::STACK, memory_byte, memory_byte).unwrap();
+	let stack_byte = args.stack.unwrap_or(memory_byte);
+	setrlimit(Resource::STACK, stack_byte, stack_byte).unwrap();
 
 	setrlimit(Resource::NPROC, 1, 1).unwrap();
 }
@@ -108,7 +123,7 @@ fn main() {
 	}
 	set_rlimit(&args);
 	if args.sandbox.unwrap_or(false) {
-		set_seccomp();
+		set_seccomp(args.file_io.unwrap_or(false));
 	}
 
 	let err = exec::Command::new(args.run).exec();
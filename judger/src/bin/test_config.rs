@@ -7,7 +7,7 @@ fn main() {
 	println!(
 		"{}",
 		to_string(&Request {
-			code:    Code {
+			solution:      Solution::Code(Code {
 				language: Language {
 					name:      "Rust".to_string(),
 					file_name: "main.rs".to_string(),
@@ -17,24 +17,40 @@ fn main() {
 						.collect(),
 				},
 				source:   std::fs::read_to_string("tests/hello.rs").unwrap(),
-			},
-			sandbox: true,
-			cases:   (0..2)
+			}),
+			sandbox:       true,
+			cases:         (0..2)
 				.map(|id| Case {
 					uid:          id as u64,
 					score:        50.0,
+					input:        format!("in{}", id),
+					answer:       format!("ans{}", id),
 					time_limit:   1_000_000 + id * 10_000_000,
 					memory_limit: 64 * 1048576,
-					dependency:   Vec::new(),
-					pack_score:   50.0,
+					stack_limit:  8 * 1048576,
+					sample:       false,
+					pretest:      false,
 				})
 				.collect(),
-			checker: RemoteCommand::pack(
+			subtasks:      (0..2)
+				.map(|id| Subtask {
+					name:       format!("Subtask {}", id + 1),
+					policy:     Policy::Sum,
+					score:      50.0,
+					cases:      vec![id],
+					dependency: Vec::new(),
+				})
+				.collect(),
+			checker:       RemoteCommand::pack(
 				vec!["python3", "../checkers/standard.py", "%OUTPUT%", "%ANSWER%"]
 					.iter()
 					.map(|s| s.to_string())
 					.collect()
 			),
+			file_io:       FileIo::default(),
+			samples_first: false,
+			pretests_only: false,
+			mode:          Mode::Judge,
 		})
 		.unwrap()
 	);
@@ -8,7 +8,7 @@ pub mod workaround {
 		std::io::Read,
 	};
 
-	#[derive(Serialize, Deserialize, Debug)]
+	#[derive(Serialize, Deserialize, Debug, Clone)]
 	enum RemouteResource {
 		String(String),
 		File(String),
@@ -16,7 +16,7 @@ pub mod workaround {
 
 	pub type Command = Vec<String>;
 
-	#[derive(Serialize, Deserialize, Debug)]
+	#[derive(Serialize, Deserialize, Debug, Clone)]
 	pub struct RemoteCommand {
 		command: Vec<RemouteResource>,
 	}
@@ -69,29 +69,176 @@ pub mod judger {
 		pub command:   Vec<String>,
 	}
 
-	#[derive(Serialize, Deserialize, Debug)]
+	#[derive(Serialize, Deserialize, Debug, Clone)]
 	pub struct Code {
 		pub language: Language,
 		pub source:   String,
 	}
 
-	#[derive(Serialize, Deserialize, Debug)]
+	#[derive(Serialize, Deserialize, Debug, Clone)]
 	pub struct Case {
 		pub uid:          u64,
 		pub score:        f64,
+		/// hashes of the data files, named so in the data dir
+		pub input:        String,
+		pub answer:       String,
 		pub time_limit:   u64,
 		pub memory_limit: u64,
-		pub dependency:   Vec<u64>,
-		pub pack_score:   f64,
+		/// 0 for the address space limit of the sandbox
+		pub stack_limit:  u64,
+		/// public, always reported in full
+		#[serde(default)]
+		pub sample:       bool,
+		/// judged while the contest runs, the rest waits for system tests
+		#[serde(default)]
+		pub pretest:      bool,
+	}
+
+	/// How case results make up a subtask score
+	#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+	pub enum Policy {
+		/// full score only if every case is accepted
+		#[default]
+		#[serde(rename = "all")]
+		All,
+		/// the lowest case ratio
+		#[serde(rename = "min")]
+		Min,
+		/// case ratios weighted by case scores
+		#[serde(rename = "sum")]
+		Sum,
+	}
+	impl Policy {
+		/// subtask ratio from (case score, case ratio) pairs
+		pub fn ratio(self, cases: &[(f64, f64)]) -> f64 {
+			match self {
+				Self::All => match cases.iter().all(|&(_, ratio)| ratio >= 1.0) {
+					true => 1.0,
+					false => 0.0,
+				},
+				Self::Min => cases.iter().map(|&(_, ratio)| ratio).fold(1.0, f64::min),
+				Self::Sum => {
+					let total: f64 = cases.iter().map(|&(score, _)| score).sum();
+					match total > 0.0 {
+						true => {
+							cases
+								.iter()
+								.map(|&(score, ratio)| score * ratio)
+								.sum::<f64>() / total
+						}
+						false => 0.0,
+					}
+				}
+			}
+		}
+		/// whether the remaining cases can no longer change the subtask score
+		pub fn stops_at(self, result: Resultat, ratio: f64) -> bool {
+			match self {
+				Self::All => result != Resultat::Accepted,
+				Self::Min => ratio <= 0.0,
+				Self::Sum => false,
+			}
+		}
+	}
+
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub struct Subtask {
+		pub name:       String,
+		pub policy:     Policy,
+		pub score:      f64,
+		/// case uids
+		pub cases:      Vec<u64>,
+		/// indices of subtasks that must be accepted before this one is judged
+		pub dependency: Vec<u64>,
+	}
+
+	/// Subtask indices in an order satisfying dependencies, None if there is a cycle
+	pub fn subtask_order(subtasks: &[Subtask]) -> Option<Vec<usize>> {
+		let mut order = Vec::with_capacity(subtasks.len());
+		let mut done = vec![false; subtasks.len()];
+		while order.len() < subtasks.len() {
+			let next = (0..subtasks.len()).find(|&index| {
+				!done[index]
+					&& subtasks[index]
+						.dependency
+						.iter()
+						.all(|&dep| done.get(dep as usize) == Some(&true))
+			})?;
+			done[next] = true;
+			order.push(next);
+		}
+		return Some(order);
+	}
+
+	/// Named files replacing stdin/stdout, relative to the working directory
+	#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+	pub struct FileIo {
+		pub input:  Option<String>,
+		pub output: Option<String>,
+	}
+
+	/// What gets judged: a program to compile and run, or one output per case
+	#[derive(Serialize, Deserialize, Debug)]
+	pub enum Solution {
+		Code(Code),
+		Outputs(Vec<String>),
+	}
+
+	/// Input challenging a solution, answered by the reference solution
+	#[derive(Serialize, Deserialize, Debug)]
+	pub struct Hack {
+		pub input:     String,
+		pub reference: Code,
+		/// exits with 0 if the input satisfies the constraints, %INPUT% is the input file
+		pub validator: Option<workaround::RemoteCommand>,
+	}
+	/// Random inputs from a generator, answered by a brute force, until the code fails on one
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub struct Stress {
+		/// reads a seed on stdin, prints an input
+		pub generator:        Code,
+		pub brute:            Code,
+		/// inputs tried at most
+		pub iterations:       u64,
+		/// us, no input is generated once spent
+		pub time_budget:      u64,
+		/// us per run of the brute force, which may well be slower than the problem allows
+		pub brute_time_limit: u64,
+	}
+
+	/// What is done with the request, runs other than judging take the limits of the first case
+	#[derive(Serialize, Deserialize, Debug, Default)]
+	pub enum Mode {
+		/// on every case
+		#[default]
+		Judge,
+		/// run the code once on this input, no checker
+		Custom(String),
+		/// judge the code on this input alone
+		Hack(Hack),
+		/// judge the code on generated inputs
+		Stress(Stress),
+		/// check the input of every case with this validator, the solution is left alone
+		Validate(workaround::RemoteCommand),
 	}
 
 	/// Judge request data besides in/ans files
 	#[derive(Serialize, Deserialize, Debug)]
 	pub struct Request {
-		pub code:    Code,
-		pub sandbox: bool,
-		pub cases:   Vec<Case>,
-		pub checker: workaround::RemoteCommand,
+		pub solution:      Solution,
+		pub sandbox:       bool,
+		pub cases:         Vec<Case>,
+		pub subtasks:      Vec<Subtask>,
+		pub checker:       workaround::RemoteCommand,
+		pub file_io:       FileIo,
+		/// judge sample cases before any other, and nothing else if one fails
+		#[serde(default)]
+		pub samples_first: bool,
+		/// judge pretest cases only, subtasks without any are skipped
+		#[serde(default)]
+		pub pretests_only: bool,
+		#[serde(default)]
+		pub mode:          Mode,
 	}
 
 	// use french word resultat to differ from rust Result
@@ -117,14 +264,10 @@ pub mod judger {
 		SystemError,
 		#[serde(rename = "SPJ Error")]
 		SPJError,
+		#[serde(rename = "Partially Correct")]
+		PartiallyCorrect,
 	}
 	impl Resultat {
-		pub fn score_coef(self) -> f64 {
-			match self {
-				Self::Accepted => 1.0,
-				_ => 0.0,
-			}
-		}
 		pub fn or(self, other: Self) -> Self {
 			match (self, other) {
 				(Self::Accepted, rhs) => rhs,
@@ -138,6 +281,8 @@ pub mod judger {
 		pub result: Resultat,
 		pub time:   u64,
 		pub memory: u64,
+		/// fraction of the case score earned, in [0, 1]
+		pub ratio:  f64,
 		pub info:   String,
 	}
 	impl CaseResultInfo {
@@ -146,11 +291,64 @@ pub mod judger {
 				result: Resultat::Skipped,
 				time:   0,
 				memory: 0,
+				ratio:  0.0,
 				info:   String::new(),
 			}
 		}
 	}
 
+	/// What a custom run printed, result is Accepted if it exited normally within limits
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub struct RunOutput {
+		pub result: Resultat,
+		pub time:   u64,
+		pub memory: u64,
+		/// exit status
+		pub info:   String,
+		/// both truncated
+		pub stdout: String,
+		pub stderr: String,
+	}
+
+	/// Prefixes of what a failed case printed and was expected to print
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub struct CaseOutput {
+		pub output: String,
+		pub stderr: String,
+		pub answer: String,
+	}
+
+	/// How far a stress test went
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub struct StressOutput {
+		/// inputs tried
+		pub iterations: u64,
+		/// the first input the code failed on, judged as case 0
+		pub input:      Option<String>,
+	}
+
+	/// What became of a hack input before the code ran on it
+	#[derive(Serialize, Deserialize, Debug, Clone)]
+	pub enum HackOutput {
+		/// rejected by the validator, with its complaint
+		Invalid(String),
+		/// output of the reference solution
+		Answer(String),
+	}
+	#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+	pub struct SubtaskResult {
+		pub result: Resultat,
+		pub score:  f64,
+	}
+	impl SubtaskResult {
+		pub fn waiting() -> Self {
+			Self {
+				result: Resultat::Waiting,
+				score:  0.0,
+			}
+		}
+	}
+
 	#[derive(Serialize, Deserialize, Debug)]
 	pub enum CaseResult {
 		Waiting,
@@ -163,8 +361,20 @@ pub mod judger {
 	#[derive(Serialize, Deserialize, Debug)]
 	pub enum Update {
 		Case(u64, CaseResult),
+		/// Output(uid, output), before the Case update of a failed case
+		Output(u64, CaseOutput),
+		/// Subtask(index, result), once all its cases are done
+		Subtask(u64, SubtaskResult),
 		/// General result update (e.g. compile)
 		Compile(CaseResult),
+		/// Result of a custom run, instead of cases
+		Run(RunOutput),
+		/// Hack input checked and answered, before the Case update
+		Hack(HackOutput),
+		/// Stress test over, before the Case update of a counterexample
+		Stress(StressOutput),
+		/// Validation(uid, complaint), None if the input of the case is valid
+		Validation(u64, Option<String>),
 		/// Finish(result, score)
 		Finish(Resultat, f64),
 		/// Internal Error
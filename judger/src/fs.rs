@@ -30,6 +30,12 @@ impl File {
 	pub fn raw(&self) -> &String {
 		&self.name
 	}
+	/// join - file inside this directory
+	pub fn join(&self, name: &str) -> File {
+		File {
+			name: format!("{}/{}", self.name, name),
+		}
+	}
 }
 
 pub struct FileList {
@@ -72,11 +78,19 @@ pub struct Fs {
 	pub source:         File,
 	pub target:         File,
 	pub output:         File,
+	pub stderr:         File,
+	pub custom_input:   File,
 	pub compile_output: File,
-	pub input:          FileList,
-	pub answer:         FileList,
+	pub data:           File,
 	pub checker:        FileList,
 	pub checker_output: File,
+	pub validator:      FileList,
+	pub hack_answer:    File,
+	pub generator:      File,
+	pub brute:          File,
+	pub stress_seed:    File,
+	pub stress_answer:  File,
+	pub run_dir:        File,
 }
 
 impl Fs {
@@ -88,11 +102,19 @@ impl Fs {
 			source:         File::bind("a/source"),
 			target:         File::bind("target"),
 			output:         File::bind("a/output"),
+			stderr:         File::bind("a/stderr"),
+			custom_input:   File::bind("a/custom_input"),
 			compile_output: File::bind("a/compile_output"),
-			input:          FileList::bind("a/data/in"),
-			answer:         FileList::bind("a/data/ans"),
+			data:           File::bind("a/data"),
 			checker:        FileList::bind("a/checker"),
 			checker_output: File::bind("a/checker_output"),
+			validator:      FileList::bind("a/validator"),
+			hack_answer:    File::bind("a/hack_answer"),
+			generator:      File::bind("generator"),
+			brute:          File::bind("brute"),
+			stress_seed:    File::bind("a/stress_seed"),
+			stress_answer:  File::bind("a/stress_answer"),
+			run_dir:        File::bind("box"),
 		});
 	}
 }
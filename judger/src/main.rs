@@ -8,10 +8,13 @@
 use {
 	anyhow::{anyhow, Error, Result},
 	cond::cond,
-	judger::{fs::Fs, judger::*, workaround},
+	judger::{
+		fs::{File, Fs},
+		judger::*,
+		workaround,
+	},
 	serde_json::{from_str, to_string},
 	std::{
-		collections::HashSet,
 		io::Read,
 		os::unix::process::ExitStatusExt,
 		process::{Child, Command, ExitStatus, Stdio},
@@ -102,6 +105,7 @@ fn compile<F: FnMut(Update)>(fs: &Fs, code: &Code, mut send: F) -> Result<()> {
 				result: Resultat::CompilationSuccess,
 				time: time.as_millis() as u64,
 				memory,
+				ratio: 0.0,
 				info: fs.compile_output.get()?,
 			})));
 		}
@@ -110,6 +114,7 @@ fn compile<F: FnMut(Update)>(fs: &Fs, code: &Code, mut send: F) -> Result<()> {
 				result: Resultat::CompilationError,
 				time: time.as_millis() as u64,
 				memory,
+				ratio: 0.0,
 				info: fs.compile_output.get()?,
 			})));
 			send(Update::Finish(Resultat::CompilationError, 0.0));
@@ -119,105 +124,483 @@ fn compile<F: FnMut(Update)>(fs: &Fs, code: &Code, mut send: F) -> Result<()> {
 	return Ok(());
 }
 
-fn run_case<F: FnMut(CaseResult)>(
+// recreate the per-case working directory of the sandboxed program
+fn prepare_run_dir(fs: &Fs) -> Result<()> {
+	let run_dir = fs.run_dir.raw();
+	if std::path::Path::new(run_dir).exists() {
+		std::fs::remove_dir_all(run_dir)?;
+	}
+	std::fs::create_dir(run_dir)?;
+	// the sandbox drops to uid 2000, which must be able to create output files
+	// failing here means we are not root, and the sandbox cannot drop either
+	let _ = std::os::unix::fs::chown(run_dir, Some(2000), Some(2000));
+	return Ok(());
+}
+
+/// run target on input_file in the sandbox, output goes to fs.output and fs.stderr
+/// returns (status, time in us, memory)
+fn execute(
 	fs: &Fs,
+	target: &File,
 	sandbox: bool,
+	file_io: &FileIo,
 	case: &Case,
-	checker: &workaround::Command,
-	mut send_case: F,
-) -> Result<()> {
-	send_case(CaseResult::Running);
-
-	let input_file = &fs.input.at(case.uid);
+	input_file: &File,
+) -> Result<(ExitStatus, u64, u64)> {
 	let output_file = &fs.output;
-	let answer_file = &fs.answer.at(case.uid);
 
 	let runner = format!(
 		"{}/sandbox",
 		std::env::var("JUDGER_BIN_DIR").unwrap_or("/app/target/release".to_string())
 	);
-	
+
+	prepare_run_dir(fs)?;
+	let stdin = match &file_io.input {
+		Some(name) => {
+			std::fs::copy(input_file.raw(), fs.run_dir.join(name).raw())?;
+			Stdio::null()
+		}
+		None => Stdio::from(input_file.getter()?),
+	};
+	let stdout = match &file_io.output {
+		Some(_) => Stdio::null(),
+		None => Stdio::from(output_file.setter()?),
+	};
+
+	let stack = match case.stack_limit {
+		0 => Vec::new(),
+		stack => vec!["--stack".to_string(), format!("{}", stack)],
+	};
 	let mut child = Command::new(runner)
 		.args(vec![
 			"-r",
-			&format!("./{}", fs.target.raw()),
+			&format!("../{}", target.raw()),
 			"-t",
 			&format!("{}", case.time_limit),
 			"-m",
 			&format!("{}", case.memory_limit),
 			"-s",
 			&format!("{}", sandbox),
+			"-f",
+			&format!("{}", file_io.input.is_some() || file_io.output.is_some()),
 		])
-		.stdin(Stdio::from(input_file.getter()?))
-		.stdout(Stdio::from(output_file.setter()?))
-		.stderr(Stdio::null())
+		.args(&stack)
+		.current_dir(fs.run_dir.raw())
+		.stdin(stdin)
+		.stdout(stdout)
+		.stderr(Stdio::from(fs.stderr.setter()?))
 		.spawn()?;
-		
+
 	let timeout = Duration::from_micros(case.time_limit + 1_000_000);
 	let Usage {
 		status,
 		time,
 		memory,
 	} = child.wait_usage_timeout(timeout)?;
-	let time = time.as_micros() as u64;
 
-	let mut send = |result, info| {
+	// a missing output file is judged as an empty output
+	if let Some(name) = &file_io.output {
+		if std::fs::copy(fs.run_dir.join(name).raw(), output_file.raw()).is_err() {
+			output_file.set("")?;
+		}
+	}
+
+	return Ok((status, time.as_micros() as u64, memory));
+}
+
+/// run target on input and check the output against answer, with the limits of case
+fn run_case<F: FnMut(CaseResult)>(
+	fs: &Fs,
+	sandbox: bool,
+	file_io: &FileIo,
+	case: &Case,
+	(input, answer): (&File, &File),
+	checker: &workaround::Command,
+	mut send_case: F,
+) -> Result<()> {
+	send_case(CaseResult::Running);
+
+	let (status, time, memory) = execute(fs, &fs.target, sandbox, file_io, case, input)?;
+
+	let mut send = |result, ratio, info| {
 		send_case(CaseResult::Finished(CaseResultInfo {
 			result,
 			time,
 			memory,
+			ratio,
 			info,
 		}))
 	};
-	let status_info = |status: ExitStatus| match status.code() {
-		None => match status.signal().unwrap() {
-			31 => "Dangerous Syscall".to_string(),
-			_ => format!("killed by signal {}", status.signal().unwrap()),
-		},
-		Some(code) => format!("exit with code {}", code),
-	};
-	let run_checker = || -> Result<ExitStatus> {
-		let mut checker_command_it = checker.into_iter();
-		let mut checker_process =
-			Command::new(checker_command_it.next().ok_or(anyhow!("empty spj"))?)
-				.args(checker_command_it.map(|entry| match entry.as_str() {
-					"%INPUT%" => input_file.raw(),
-					"%OUTPUT%" => fs.output.raw(),
-					"%ANSWER%" => answer_file.raw(),
-					_ => entry,
-				}))
-				.stdin(Stdio::null())
-				.stdout(Stdio::from(fs.checker_output.setter()?))
-				.stderr(Stdio::null())
-				.spawn()?;
-		return Ok(checker_process
-			.wait_usage_timeout(Duration::from_secs(1))?
-			.status);
-	};
 
 	cond! {
-	  memory > case.memory_limit => send(Resultat::MemoryLimitExceeded, status_info(status)),
-	  time > case.time_limit => send(Resultat::TimeLimitExceeded, status_info(status)),
-	  !status.success() => send(Resultat::RuntimeError, status_info(status)),
+	  memory > case.memory_limit => send(Resultat::MemoryLimitExceeded, 0.0, status_info(status)),
+	  time > case.time_limit => send(Resultat::TimeLimitExceeded, 0.0, status_info(status)),
+	  !status.success() => send(Resultat::RuntimeError, 0.0, status_info(status)),
 	  _ => {
-	  let checker_status = run_checker()?;
-	  match checker_status.success() {
-		false => send(Resultat::SPJError, format!("checker exit with {}", status_info(checker_status))),
-		true => {
-					let checker_output = fs.checker_output.get()?;
-					let mut iter = checker_output.split("\n");
-					match iter.next() {
-			Some("Accepted") => send(Resultat::Accepted, iter.next().unwrap_or("").to_string()),
-			_ => send(Resultat::WrongAnswer, iter.next().unwrap_or("").to_string()),
-		  };
-		}
+		let (result, ratio, info) = run_checker(fs, input, answer, checker)?;
+		send(result, ratio, info);
 	  }
 	}
+
+	return Ok(());
+}
+
+/// run or check one case, reporting it and what a failure printed
+/// returns (result, ratio)
+fn judge_case(
+	fs: &Fs,
+	solution: &Solution,
+	sandbox: bool,
+	file_io: &FileIo,
+	uid: u64,
+	case: &Case,
+	checker: &workaround::Command,
+) -> Result<(Resultat, f64)> {
+	let mut judged = None;
+	let send_case = |data: CaseResult| {
+		if let CaseResult::Finished(info) = &data {
+			judged = Some((info.result, info.ratio));
+			// best effort, the verdict matters more than what led to it
+			if info.result != Resultat::Accepted {
+				if let Ok(output) = case_output(fs, case) {
+					send(Update::Output(uid, output));
+				}
+			}
+		}
+		send(Update::Case(uid, data));
+	};
+	match solution {
+		Solution::Code(_) => run_case(
+			fs,
+			sandbox,
+			file_io,
+			case,
+			(&fs.data.join(&case.input), &fs.data.join(&case.answer)),
+			checker,
+			send_case,
+		)?,
+		// outputs are checked against the cases when submitted, but a rejudge against
+		// another version may have more cases: a missing output is judged as an empty one
+		Solution::Outputs(outputs) => check_case(
+			fs,
+			outputs.get(uid as usize).map_or("", |output| output),
+			case,
+			checker,
+			send_case,
+		)?,
+	}
+	return judged.ok_or(anyhow!("case {} finished without result", uid));
+}
+
+/// bytes of stdout/stderr kept from a custom run
+const RUN_OUTPUT_LIMIT: u64 = 64 * 1024;
+/// bytes of output/stderr/answer kept from each failed case
+const CASE_OUTPUT_LIMIT: u64 = 4 * 1024;
+
+/// leading bytes of file, a missing file reads as empty
+fn read_prefix(file: &File, limit: u64) -> Result<String> {
+	let mut buf = Vec::new();
+	if let Ok(reader) = file.getter() {
+		reader.take(limit).read_to_end(&mut buf)?;
+	}
+	return Ok(String::from_utf8_lossy(&buf).to_string());
+}
+
+/// what is kept of a failed case, read before the next case overwrites it
+fn case_output(fs: &Fs, case: &Case) -> Result<CaseOutput> {
+	return Ok(CaseOutput {
+		output: read_prefix(&fs.output, CASE_OUTPUT_LIMIT)?,
+		stderr: read_prefix(&fs.stderr, CASE_OUTPUT_LIMIT)?,
+		answer: read_prefix(&fs.data.join(&case.answer), CASE_OUTPUT_LIMIT)?,
+	});
+}
+
+/// run on a given input and report what the program printed
+fn run_custom(
+	fs: &Fs,
+	sandbox: bool,
+	file_io: &FileIo,
+	case: &Case,
+	input: &str,
+) -> Result<RunOutput> {
+	fs.custom_input.set(input)?;
+	let (status, time, memory) = execute(fs, &fs.target, sandbox, file_io, case, &fs.custom_input)?;
+	return Ok(RunOutput {
+		result: cond! {
+			memory > case.memory_limit => Resultat::MemoryLimitExceeded,
+			time > case.time_limit => Resultat::TimeLimitExceeded,
+			!status.success() => Resultat::RuntimeError,
+			_ => Resultat::Accepted,
+		},
+		time,
+		memory,
+		info: status_info(status),
+		stdout: read_prefix(&fs.output, RUN_OUTPUT_LIMIT)?,
+		stderr: read_prefix(&fs.stderr, RUN_OUTPUT_LIMIT)?,
+	});
+}
+
+/// seconds a validator may take on one input
+const VALIDATOR_TIMEOUT: u64 = 10;
+
+/// run the validator on input, returns its complaint if the input is invalid
+fn run_validator(fs: &Fs, validator: &workaround::Command, input: &File) -> Result<Option<String>> {
+	let mut validator_it = validator.iter();
+	let output = fs.checker_output.setter()?;
+	let mut validator_process =
+		Command::new(validator_it.next().ok_or(anyhow!("empty validator"))?)
+			.args(validator_it.map(|entry| match entry.as_str() {
+				"%INPUT%" => input.raw().clone(),
+				_ => entry.clone(),
+			}))
+			.stdin(Stdio::from(input.getter()?))
+			.stdout(Stdio::from(output.try_clone()?))
+			.stderr(Stdio::from(output))
+			.spawn()?;
+	let status = validator_process
+		.wait_usage_timeout(Duration::from_secs(VALIDATOR_TIMEOUT))?
+		.status;
+	if status.success() {
+		return Ok(None);
+	}
+	let complaint = read_prefix(&fs.checker_output, CASE_OUTPUT_LIMIT)?;
+	return Ok(Some(match complaint.trim() {
+		"" => format!("validator exit with {}", status_info(status)),
+		complaint => complaint.to_string(),
+	}));
+}
+
+/// compile a helper program into fs.target without reporting, fs.source is kept
+fn compile_quietly(fs: &mut Fs, code: &Code) -> Result<CaseResultInfo> {
+	let source = std::mem::replace(&mut fs.source, File::bind(&code.language.file_name));
+	fs.source.set(&code.source)?;
+	let mut compiled = None;
+	compile(fs, code, |update| {
+		if let Update::Compile(CaseResult::Finished(info)) = update {
+			compiled = Some(info);
+		}
+	})?;
+	fs.source = source;
+	return compiled.ok_or(anyhow!("compilation finished without result"));
+}
+
+/// run a program of the problem setter with the limits of case, failing unless it exits normally
+fn run_helper(
+	fs: &Fs,
+	(name, target): (&str, &File),
+	sandbox: bool,
+	file_io: &FileIo,
+	case: &Case,
+	input: &File,
+) -> Result<()> {
+	let (status, time, memory) = execute(fs, target, sandbox, file_io, case, input)?;
+	cond! {
+		memory > case.memory_limit => return Err(anyhow!("{} exceeds memory limit", name)),
+		time > case.time_limit => return Err(anyhow!("{} exceeds time limit", name)),
+		!status.success() => return Err(anyhow!("{} {}", name, status_info(status))),
+		_ => {},
+	}
+	return Ok(());
+}
+
+/// compile and run the reference solution on fs.custom_input, keeping its output in fs.hack_answer
+fn run_reference(
+	fs: &mut Fs,
+	sandbox: bool,
+	file_io: &FileIo,
+	case: &Case,
+	reference: &Code,
+) -> Result<String> {
+	if compile_quietly(fs, reference)?.result != Resultat::CompilationSuccess {
+		return Err(anyhow!("reference solution fails to compile"));
 	}
 
+	run_helper(
+		fs,
+		("reference solution", &fs.target),
+		sandbox,
+		file_io,
+		case,
+		&fs.custom_input,
+	)?;
+	std::fs::copy(fs.output.raw(), fs.hack_answer.raw())?;
+	return fs.hack_answer.get();
+}
+
+/// bytes kept of a stress test counterexample
+const STRESS_INPUT_LIMIT: u64 = 1024 * 1024;
+
+/// judge the code on generated inputs answered by the brute force until it fails on one,
+/// the generator is given seeds 1, 2, ... on stdin
+fn run_stress(
+	fs: &Fs,
+	sandbox: bool,
+	file_io: &FileIo,
+	case: &Case,
+	stress: &Stress,
+	checker: &workaround::Command,
+) -> Result<()> {
+	let started = Instant::now();
+	let budget = Duration::from_micros(stress.time_budget);
+	let mut iterations = 0;
+	while iterations < stress.iterations && started.elapsed() < budget {
+		iterations += 1;
+		fs.stress_seed.set(format!("{}\n", iterations))?;
+		// generated inputs go to stdout whatever the problem reads from
+		run_helper(
+			fs,
+			("generator", &fs.generator),
+			sandbox,
+			&FileIo::default(),
+			case,
+			&fs.stress_seed,
+		)?;
+		std::fs::copy(fs.output.raw(), fs.custom_input.raw())?;
+		run_helper(
+			fs,
+			("brute force", &fs.brute),
+			sandbox,
+			file_io,
+			&Case {
+				time_limit: stress.brute_time_limit,
+				..case.clone()
+			},
+			&fs.custom_input,
+		)?;
+		std::fs::copy(fs.output.raw(), fs.stress_answer.raw())?;
+
+		let mut failed = None;
+		run_case(
+			fs,
+			sandbox,
+			file_io,
+			case,
+			(&fs.custom_input, &fs.stress_answer),
+			checker,
+			|data| {
+				if let CaseResult::Finished(info) = data {
+					if info.result != Resultat::Accepted {
+						failed = Some(info);
+					}
+				}
+			},
+		)?;
+		if let Some(info) = failed {
+			let result = info.result;
+			send(Update::Output(
+				0,
+				CaseOutput {
+					output: read_prefix(&fs.output, CASE_OUTPUT_LIMIT)?,
+					stderr: read_prefix(&fs.stderr, CASE_OUTPUT_LIMIT)?,
+					answer: read_prefix(&fs.stress_answer, CASE_OUTPUT_LIMIT)?,
+				},
+			));
+			send(Update::Stress(StressOutput {
+				iterations,
+				input: Some(read_prefix(&fs.custom_input, STRESS_INPUT_LIMIT)?),
+			}));
+			send(Update::Case(0, CaseResult::Finished(info)));
+			send(Update::Finish(result, 0.0));
+		}
+	}
+	send(Update::Stress(StressOutput {
+		iterations,
+		input: None,
+	}));
+	send(Update::Finish(Resultat::Accepted, 0.0));
+	return Ok(());
+}
+
+/// judge an uploaded output directly, without running anything
+fn check_case<F: FnMut(CaseResult)>(
+	fs: &Fs,
+	output: &str,
+	case: &Case,
+	checker: &workaround::Command,
+	mut send_case: F,
+) -> Result<()> {
+	send_case(CaseResult::Running);
+	fs.output.set(output)?;
+	let (result, ratio, info) = run_checker(
+		fs,
+		&fs.data.join(&case.input),
+		&fs.data.join(&case.answer),
+		checker,
+	)?;
+	send_case(CaseResult::Finished(CaseResultInfo {
+		result,
+		time: 0,
+		memory: 0,
+		ratio,
+		info,
+	}));
 	return Ok(());
 }
 
+fn status_info(status: ExitStatus) -> String {
+	match status.code() {
+		None => match status.signal().unwrap() {
+			31 => "Dangerous Syscall".to_string(),
+			_ => format!("killed by signal {}", status.signal().unwrap()),
+		},
+		Some(code) => format!("exit with code {}", code),
+	}
+}
+
+/// run checker on fs.output, returns (result, score ratio, info)
+/// checker prints "Accepted", "Partial <ratio>" or anything else (Wrong Answer),
+/// followed by an info line
+fn run_checker(
+	fs: &Fs,
+	input: &File,
+	answer: &File,
+	checker: &workaround::Command,
+) -> Result<(Resultat, f64, String)> {
+	let mut checker_command_it = checker.iter();
+	let mut checker_process = Command::new(checker_command_it.next().ok_or(anyhow!("empty spj"))?)
+		.args(checker_command_it.map(|entry| match entry.as_str() {
+			"%INPUT%" => input.raw().clone(),
+			"%OUTPUT%" => fs.output.raw().clone(),
+			"%ANSWER%" => answer.raw().clone(),
+			_ => entry.clone(),
+		}))
+		.stdin(Stdio::null())
+		.stdout(Stdio::from(fs.checker_output.setter()?))
+		.stderr(Stdio::null())
+		.spawn()?;
+	let checker_status = checker_process
+		.wait_usage_timeout(Duration::from_secs(1))?
+		.status;
+	if !checker_status.success() {
+		return Ok((
+			Resultat::SPJError,
+			0.0,
+			format!("checker exit with {}", status_info(checker_status)),
+		));
+	}
+
+	let checker_output = fs.checker_output.get()?;
+	let mut iter = checker_output.split("\n");
+	let verdict = iter.next().unwrap_or("");
+	let info = iter.next().unwrap_or("").to_string();
+	return Ok(match verdict {
+		"Accepted" => (Resultat::Accepted, 1.0, info),
+		_ => match verdict.strip_prefix("Partial ") {
+			None => (Resultat::WrongAnswer, 0.0, info),
+			Some(ratio) => match ratio.trim().parse::<f64>() {
+				Ok(ratio) if (0.0..=1.0).contains(&ratio) => {
+					(Resultat::PartiallyCorrect, ratio, info)
+				}
+				_ => (
+					Resultat::SPJError,
+					0.0,
+					format!("checker gives ill ratio {:?}", ratio),
+				),
+			},
+		},
+	});
+}
+
 // send takes onwership as a continuation
 fn send(data: Update) {
 	println!("{}", to_string(&data).unwrap());
@@ -236,52 +619,231 @@ fn main() {
 			let Request {
 				cases,
 				sandbox,
-				code,
+				solution,
+				subtasks,
 				checker,
+				file_io,
+				samples_first,
+				pretests_only,
+				mode,
 			} = || -> Result<Request> {
 				let mut buf: String = String::new();
 				std::io::stdin().read_to_string(&mut buf)?;
 				return Ok(from_str(&buf)?);
 			}()?;
 
-			let fs = {
+			let mut fs = {
 				let mut fs =
 					Fs::bind(&std::env::var("JUDGER_WORK_DIR").unwrap_or("/work".to_string()))?;
 				// cpp compilers require filename to determine file type
-				fs.source = judger::fs::File::bind(&code.language.file_name);
+				if let Solution::Code(code) = &solution {
+					fs.source = judger::fs::File::bind(&code.language.file_name);
+				}
 				fs
 			};
 
 			// unpack checker
 			let checker = checker.unpack(fs.checker.iter().map(|f| f.raw().clone()))?;
 
-			// save & compile source
-			fs.source.set(&code.source)?;
-			compile(&fs, &code, send)?;
+			// nothing is compiled or run when only checking the case inputs
+			if let Mode::Validate(validator) = &mode {
+				let validator = validator
+					.clone()
+					.unpack(fs.validator.iter().map(|f| f.raw().clone()))?;
+				for case in &cases {
+					let complaint = run_validator(&fs, &validator, &fs.data.join(&case.input))?;
+					send(Update::Validation(case.uid, complaint));
+				}
+				send(Update::Finish(Resultat::Accepted, 0.0));
+			}
+
+			// check and answer a hack input before the code to judge is compiled over
+			if let Mode::Hack(Hack {
+				input,
+				reference,
+				validator,
+			}) = &mode
+			{
+				let case = cases.first().ok_or(anyhow!("hack without limits"))?;
+				fs.custom_input.set(input)?;
+				if let Some(validator) = validator {
+					let validator = validator
+						.clone()
+						.unpack(fs.validator.iter().map(|f| f.raw().clone()))?;
+					if let Some(complaint) = run_validator(&fs, &validator, &fs.custom_input)? {
+						send(Update::Hack(HackOutput::Invalid(complaint)));
+						send(Update::Finish(Resultat::Skipped, 0.0));
+					}
+				}
+				let answer = run_reference(&mut fs, sandbox, &file_io, case, reference)?;
+				send(Update::Hack(HackOutput::Answer(answer)));
+			}
+
+			// compile the stress test programs before the code to judge is compiled over
+			if let Mode::Stress(Stress {
+				generator,
+				brute,
+				..
+			}) = &mode
+			{
+				let programs = [
+					("generator", generator, fs.generator.raw().clone()),
+					("brute force", brute, fs.brute.raw().clone()),
+				];
+				for (name, code, target) in programs {
+					let info = compile_quietly(&mut fs, code)?;
+					// reported as if the code to judge failed, they come together
+					if info.result != Resultat::CompilationSuccess {
+						send(Update::Compile(CaseResult::Finished(CaseResultInfo {
+							info: format!("{}: {}", name, info.info),
+							..info
+						})));
+						send(Update::Finish(Resultat::CompilationError, 0.0));
+					}
+					std::fs::rename(fs.target.raw(), target)?;
+				}
+			}
 
-			// run cases
+			// save & compile source, output-only solutions have nothing to compile
+			match &solution {
+				Solution::Code(code) => {
+					fs.source.set(&code.source)?;
+					compile(&fs, code, send)?;
+				}
+				Solution::Outputs(_) => send(Update::Compile(CaseResult::Skipped)),
+			}
+
+			match &mode {
+				Mode::Judge | Mode::Validate(_) => {}
+				Mode::Custom(input) => {
+					let case = cases.first().ok_or(anyhow!("custom run without limits"))?;
+					let output = run_custom(&fs, sandbox, &file_io, case, input)?;
+					let result = output.result;
+					send(Update::Run(output));
+					send(Update::Finish(result, 0.0));
+				}
+				Mode::Hack(_) => {
+					let case = cases.first().ok_or(anyhow!("hack without limits"))?;
+					let mut result = None;
+					run_case(
+						&fs,
+						sandbox,
+						&file_io,
+						case,
+						(&fs.custom_input, &fs.hack_answer),
+						&checker,
+						|data| {
+							if let CaseResult::Finished(info) = &data {
+								result = Some(info.result);
+							}
+							send(Update::Case(0, data));
+						},
+					)?;
+					send(Update::Finish(
+						result.ok_or(anyhow!("hack finished without result"))?,
+						0.0,
+					));
+				}
+				Mode::Stress(stress) => {
+					let case = cases.first().ok_or(anyhow!("stress test without limits"))?;
+					run_stress(&fs, sandbox, &file_io, case, stress, &checker)?;
+				}
+			}
+
+			// run subtasks in dependency order, judging each case at most once
+			let order = subtask_order(&subtasks).ok_or(anyhow!("subtask dependency cycle"))?;
+			let mut case_results: Vec<Option<(Resultat, f64)>> =
+				cases.iter().map(|_| None).collect();
+			// dependents of unsatisfied subtasks are skipped
+			let mut satisfied: Vec<bool> = subtasks.iter().map(|_| false).collect();
 			let mut score: f64 = 0.0;
-			let mut accepted_set = HashSet::<u64>::new();
 			let mut general_result = Resultat::Accepted;
-			for (id, case) in cases.iter().enumerate() {
-				let id = id as u64;
-				if case.dependency.iter().any(|id| !accepted_set.contains(id)) {
-					send(Update::Case(
-						id,
-						CaseResult::Finished(CaseResultInfo::skipped()),
-					));
-				} else {
-					run_case(&fs, sandbox, case, &checker, |data: CaseResult| {
-						if let CaseResult::Finished(info) = &data {
-							if info.result == Resultat::Accepted {
-								accepted_set.insert(id);
-							}
-							score += info.result.score_coef() * case.pack_score;
-							general_result = general_result.or(info.result);
+
+			// a failed sample skips everything not judged yet
+			let mut halted = false;
+			if samples_first {
+				for (uid, case) in cases
+					.iter()
+					.enumerate()
+					.filter(|(_, case)| case.sample && (case.pretest || !pretests_only))
+				{
+					let judged = judge_case(
+						&fs, &solution, sandbox, &file_io, uid as u64, case, &checker,
+					)?;
+					general_result = general_result.or(judged.0);
+					case_results[uid] = Some(judged);
+					if judged.0 != Resultat::Accepted {
+						halted = true;
+						break;
+					}
+				}
+			}
+
+			for index in order {
+				let subtask = &subtasks[index];
+				let blocked = subtask
+					.dependency
+					.iter()
+					.any(|&dep| !satisfied[dep as usize]);
+
+				let mut stopped = blocked || halted;
+				let mut result = Resultat::Accepted;
+				let mut ratios = Vec::<(f64, f64)>::new();
+				for &uid in &subtask.cases {
+					let case = &cases[uid as usize];
+					// left for system tests, counts for nothing now
+					if pretests_only && !case.pretest {
+						if case_results[uid as usize].is_none() {
+							send(Update::Case(
+								uid,
+								CaseResult::Finished(CaseResultInfo::skipped()),
+							));
+						}
+						continue;
+					}
+					if stopped {
+						if case_results[uid as usize].is_none() {
+							send(Update::Case(
+								uid,
+								CaseResult::Finished(CaseResultInfo::skipped()),
+							));
 						}
-						send(Update::Case(id, data));
-					})?;
+						result = result.or(Resultat::Skipped);
+						ratios.push((case.score, 0.0));
+						continue;
+					}
+
+					if case_results[uid as usize].is_none() {
+						let judged =
+							judge_case(&fs, &solution, sandbox, &file_io, uid, case, &checker)?;
+						general_result = general_result.or(judged.0);
+						case_results[uid as usize] = Some(judged);
+					}
+
+					let (case_result, ratio) = case_results[uid as usize]
+						.ok_or(anyhow!("case {} finished without result", uid))?;
+					result = result.or(case_result);
+					ratios.push((case.score, ratio));
+					stopped = subtask.policy.stops_at(case_result, ratio);
 				}
+
+				let subtask_result = match ratios.is_empty() {
+					// no pretest in it, which holds back none of its dependents
+					true => SubtaskResult {
+						result: Resultat::Skipped,
+						score:  0.0,
+					},
+					false => SubtaskResult {
+						result,
+						score: subtask.policy.ratio(&ratios) * subtask.score,
+					},
+				};
+				satisfied[index] = match ratios.is_empty() {
+					true => !blocked,
+					false => result == Resultat::Accepted,
+				};
+				score += subtask_result.score;
+				send(Update::Subtask(index as u64, subtask_result));
 			}
 
 			send(Update::Finish(general_result, score));
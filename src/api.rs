@@ -1,2 +1,10 @@
+pub mod contests;
+pub mod data;
+pub mod hacks;
 pub mod jobs;
-pub mod users;
\ No newline at end of file
+pub mod problems;
+pub mod rejudges;
+pub mod runs;
+pub mod stress_tests;
+pub mod teams;
+pub mod users;
@@ -0,0 +1,1102 @@
+use {
+	crate::{
+		api::jobs,
+		callcc::{callcc, callcc_ret, KEntrance},
+		common, config, contest, judger, response, service, team, user,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	chrono::Utc,
+	serde::Deserialize,
+	serde_json::json,
+	std::{
+		collections::{HashMap, HashSet},
+		sync::Arc,
+	},
+};
+
+#[derive(Deserialize)]
+struct Request {
+	/// update this contest instead of creating one
+	id:      Option<u64>,
+	#[serde(flatten)]
+	contest: contest::RawContest,
+}
+
+fn contest_to_response(contest: &contest::Contest) -> serde_json::Value {
+	json!({
+		"id": contest.id,
+		"name": &contest.raw.name,
+		"from": contest.raw.from.format(common::TIME_FORMAT).to_string(),
+		"to": contest.raw.to.format(common::TIME_FORMAT).to_string(),
+		"problem_ids": &contest.raw.problem_ids,
+		"problems": &contest.raw.problems,
+		"user_ids": &contest.raw.user_ids,
+		"submission_limit": contest.raw.submission_limit,
+		"pretests": contest.raw.pretests,
+		"hacking": contest.raw.hacking.as_ref().map(|hacking| json!({
+			"until": hacking.until.format(common::TIME_FORMAT).to_string(),
+			"score": hacking.score,
+			"penalty": hacking.penalty,
+			"system_tests": hacking.system_tests,
+		})),
+		"icpc": contest.raw.icpc.as_ref().map(|icpc| json!({
+			"penalty": icpc.penalty,
+			"penalized": &icpc.penalized,
+			"freeze": icpc.freeze,
+		})),
+		"unfrozen": contest.unfrozen(),
+		"registration": contest.raw.registration.as_ref().map(|registration| json!({
+			"until": registration.until.map(|until| until.format(common::TIME_FORMAT).to_string()),
+			"approval": registration.approval,
+			// the codes themselves stay secret
+			"invitation": !registration.codes.is_empty(),
+		})),
+		"feedback": contest.raw.feedback,
+		"phase": contest.phase(),
+	})
+}
+
+fn not_found(id: u64) -> HttpResponse {
+	HttpResponse::NotFound().json(response::Error {
+		code:    3,
+		reason:  "ERR_NOT_FOUND".to_string(),
+		message: format!("Contest {} not found.", id),
+	})
+}
+
+fn participation_not_found(id: u64, user_id: u64) -> HttpResponse {
+	HttpResponse::NotFound().json(response::Error {
+		code:    3,
+		reason:  "ERR_NOT_FOUND".to_string(),
+		message: format!(
+			"Virtual participation of user {} in contest {} not found.",
+			user_id, id
+		),
+	})
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+/// whether job may be submitted to its contest, Ok(true) if judged on pretests only
+///
+/// users in a running virtual participation submit as if the contest ran, judged in full
+pub(crate) fn check_submission(submission: &jobs::Submission) -> Result<bool, HttpResponse> {
+	if submission.contest_id == 0 {
+		return Ok(false);
+	}
+	let contest = contest::get(submission.contest_id).ok_or(not_found(submission.contest_id))?;
+	let practice = contest::participation(contest.id, submission.user_id)
+		.filter(|participation| participation.phase() == contest::Phase::Running);
+	// limits are shared by a team
+	let members = match (&practice, contest.entrant_of(submission.user_id)) {
+		(None, Some(entrant)) => entrant.members(),
+		(None, None) => {
+			return Err(invalid_argument(format!(
+				"User {} is not in contest {}.",
+				submission.user_id, contest.id
+			)));
+		}
+		(Some(_), _) => vec![submission.user_id],
+	};
+	let Some(problem) = contest.problem(submission.problem_id) else {
+		return Err(invalid_argument(format!(
+			"Problem {} is not in contest {}.",
+			submission.problem_id, contest.id
+		)));
+	};
+	if practice.is_none() && contest.phase() != contest::Phase::Running {
+		return Err(invalid_argument(format!(
+			"Contest {} is not running.",
+			contest.id
+		)));
+	}
+	let submitted = service::get_list()
+		.iter()
+		.filter(|job| {
+			let job = job.lock().unwrap();
+			job.raw.contest_id == submission.contest_id
+				&& members.contains(&job.raw.user_id)
+				&& job.raw.problem_id == submission.problem_id
+				&& practice
+					.as_ref()
+					.is_none_or(|participation| job.created_time >= participation.from)
+		})
+		.count();
+	let limit = problem
+		.submission_limit
+		.unwrap_or(contest.raw.submission_limit);
+	if submitted as u64 >= limit {
+		return Err(HttpResponse::BadRequest().json(response::Error {
+			code:    4,
+			reason:  "ERR_RATE_LIMIT".to_string(),
+			message: format!("Submission limit of contest {} reached.", contest.id),
+		}));
+	}
+	return Ok(contest.raw.pretests && practice.is_none());
+}
+
+/// create a contest, or update the one with the given id
+#[post("/contests")]
+fn post(req: web::Json<Request>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let Request {
+		id,
+		mut contest,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		if id == Some(0) {
+			return Err(invalid_argument("Contest 0 cannot be changed.".to_string()));
+		}
+		if contest.from > contest.to {
+			return Err(invalid_argument(
+				"Contest ends before it starts.".to_string(),
+			));
+		}
+		if contest
+			.hacking
+			.as_ref()
+			.is_some_and(|hacking| hacking.until < contest.to)
+		{
+			return Err(invalid_argument(
+				"Hacking ends before the contest.".to_string(),
+			));
+		}
+		// problem_ids and problems must agree if both given
+		let problem_ids = contest
+			.problems
+			.iter()
+			.map(|problem| problem.problem_id)
+			.collect::<Vec<_>>();
+		if contest.problems.is_empty() {
+			contest.problems = contest
+				.problem_ids
+				.iter()
+				.map(|&problem_id| contest::RawContestProblem::new(problem_id))
+				.collect();
+		} else if contest.problem_ids.is_empty() {
+			contest.problem_ids = problem_ids;
+		} else if contest.problem_ids != problem_ids {
+			return Err(invalid_argument(
+				"problem_ids differ from problems.".to_string(),
+			));
+		}
+		let distinct = |ids: &Vec<u64>| ids.iter().collect::<HashSet<_>>().len() == ids.len();
+		if !distinct(&contest.problem_ids) || !distinct(&contest.user_ids) {
+			return Err(invalid_argument("Repeated problem or user.".to_string()));
+		}
+		for (index, entry) in contest.problems.iter_mut().enumerate() {
+			let problem = config
+				.problem(entry.problem_id)
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("Problem {} not found.", entry.problem_id),
+				}))?;
+			if !entry.weight.is_finite() || entry.weight < 0.0 || entry.time_limit == Some(0) {
+				return Err(invalid_argument(format!(
+					"Ill weight or time_limit of problem {}.",
+					entry.problem_id
+				)));
+			}
+			entry.label.get_or_insert_with(|| contest::label(index));
+			entry.name.get_or_insert_with(|| problem.name.clone());
+		}
+		let labels = contest
+			.problems
+			.iter()
+			.map(|problem| &problem.label)
+			.collect::<HashSet<_>>();
+		if labels.len() != contest.problems.len() {
+			return Err(invalid_argument("Repeated problem label.".to_string()));
+		}
+		let users = user::get_list_id().len() as u64;
+		if let Some(&user_id) = contest.user_ids.iter().find(|&&user_id| user_id >= users) {
+			return Err(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("User {} not found.", user_id),
+			}));
+		}
+		let contest = match id {
+			None => contest::add(contest),
+			Some(id) => contest::replace(id, contest).ok_or(not_found(id))?,
+		};
+		k.resume(HttpResponse::Ok().json(contest_to_response(&contest)));
+		return Ok(());
+	})
+}
+
+#[get("/contests")]
+fn get() -> KEntrance<HttpResponse> {
+	callcc(move |k| {
+		// phases lock the jobs of system tests, see the lock order in contest.rs
+		let contests = contest::get_list().clone();
+		k.resume(
+			HttpResponse::Ok().json(
+				contests
+					.iter()
+					.map(|contest| contest_to_response(contest))
+					.collect::<Vec<_>>(),
+			),
+		);
+	})
+}
+
+#[get("/contests/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		k.resume(HttpResponse::Ok().json(contest_to_response(&contest)));
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize)]
+struct UnfreezeRequest {
+	/// must be an admin, proven by token
+	user_id: u64,
+	/// admin_token of the server
+	token:   Option<String>,
+}
+
+/// reveal the frozen ranklist of an ended contest to everyone
+#[post("/contests/{id}/unfreeze")]
+fn post_unfreeze(
+	id: web::Path<u64>,
+	req: web::Json<UnfreezeRequest>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	let UnfreezeRequest {
+		user_id,
+		token,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		if !config.server.admin(Some(user_id), token.as_deref()) {
+			return Err(HttpResponse::Forbidden().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("User {} cannot unfreeze contests.", user_id),
+			}));
+		}
+		if contest
+			.raw
+			.icpc
+			.as_ref()
+			.is_none_or(|icpc| icpc.freeze == 0)
+		{
+			return Err(invalid_argument(format!(
+				"Contest {} has no ranklist freeze.",
+				id
+			)));
+		}
+		if Utc::now() <= contest.raw.to {
+			return Err(HttpResponse::BadRequest().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("Contest {} has not ended.", id),
+			}));
+		}
+		contest.unfreeze();
+		k.resume(HttpResponse::Ok().json(contest_to_response(&contest)));
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize)]
+struct RegistrationRequest {
+	user_id:      u64,
+	/// register this team instead, the user must be a member
+	#[serde(default)]
+	team_id:      Option<u64>,
+	/// invitation code
+	#[serde(default)]
+	code:         Option<String>,
+	#[serde(default)]
+	display_name: Option<String>,
+}
+
+fn registration_to_response(registration: &contest::Registration) -> serde_json::Value {
+	json!({
+		"user_id": registration.user_id,
+		"team_id": registration.team_id,
+		"display_name": &registration.display_name,
+		"state": registration.state,
+		"created_time": registration.created_time.format(common::TIME_FORMAT).to_string(),
+	})
+}
+
+fn invalid_state(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 2,
+		reason: "ERR_INVALID_STATE".to_string(),
+		message,
+	})
+}
+
+/// enter a user or their team, at once with a valid code or if none is needed,
+/// otherwise once approved
+#[post("/contests/{id}/registrations")]
+fn post_registration(
+	id: web::Path<u64>,
+	req: web::Json<RegistrationRequest>,
+) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	let RegistrationRequest {
+		user_id,
+		team_id,
+		code,
+		display_name,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		user::get_list_id()
+			.get(user_id as usize)
+			.ok_or(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("User {} not found.", user_id),
+			}))?;
+		if let Some(team_id) = team_id {
+			let team =
+				team::get(team_id).ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("Team {} not found.", team_id),
+				}))?;
+			if !team.user_ids.contains(&user_id) {
+				return Err(invalid_argument(format!(
+					"User {} is not in team {}.",
+					user_id, team_id
+				)));
+			}
+		}
+		let Some(rules) = contest
+			.raw
+			.registration
+			.as_ref()
+			.filter(|_| contest.registration_open())
+		else {
+			return Err(invalid_state(format!(
+				"Contest {} is not open for registration.",
+				id
+			)));
+		};
+		let refused = |message: String| {
+			HttpResponse::Forbidden().json(response::Error {
+				code: 2,
+				reason: "ERR_INVALID_STATE".to_string(),
+				message,
+			})
+		};
+		let state = match &code {
+			Some(code) if rules.codes.contains(code) => contest::RegistrationState::Approved,
+			Some(_) => return Err(refused("Invalid invitation code.".to_string())),
+			None if rules.approval => contest::RegistrationState::Pending,
+			None if rules.codes.is_empty() => contest::RegistrationState::Approved,
+			None => {
+				return Err(refused(format!(
+					"Contest {} requires an invitation code.",
+					id
+				)));
+			}
+		};
+		let registration = contest::Registration {
+			user_id,
+			team_id,
+			display_name,
+			state,
+			created_time: Utc::now(),
+		};
+		contest
+			.register(registration.clone())
+			.map_err(invalid_state)?;
+		k.resume(HttpResponse::Ok().json(registration_to_response(&registration)));
+		return Ok(());
+	})
+}
+
+#[get("/contests/{id}/registrations")]
+fn get_registrations(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		k.resume(
+			HttpResponse::Ok().json(
+				contest
+					.registrations()
+					.iter()
+					.map(registration_to_response)
+					.collect::<Vec<_>>(),
+			),
+		);
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize)]
+struct ReviewRequest {
+	/// must be an admin, proven by token
+	user_id:  u64,
+	/// admin_token of the server
+	token:    Option<String>,
+	approved: bool,
+}
+
+/// approve or reject the pending registration of registrant
+#[post("/contests/{id}/registrations/{registrant}/review")]
+fn post_review(
+	path: web::Path<(u64, u64)>,
+	req: web::Json<ReviewRequest>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let (id, registrant) = path.into_inner();
+	let ReviewRequest {
+		user_id,
+		token,
+		approved,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		if !config.server.admin(Some(user_id), token.as_deref()) {
+			return Err(HttpResponse::Forbidden().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("User {} cannot review registrations.", user_id),
+			}));
+		}
+		let mut registrations = contest.registrations();
+		let registration = registrations
+			.iter_mut()
+			.rev()
+			.find(|registration| registration.user_id == registrant)
+			.ok_or(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!(
+					"Registration of user {} in contest {} not found.",
+					registrant, id
+				),
+			}))?;
+		if registration.state != contest::RegistrationState::Pending {
+			return Err(invalid_state(format!(
+				"Registration of user {} is reviewed already.",
+				registrant
+			)));
+		}
+		registration.state = match approved {
+			true => contest::RegistrationState::Approved,
+			false => contest::RegistrationState::Rejected,
+		};
+		let response = registration_to_response(registration);
+		drop(registrations);
+		k.resume(HttpResponse::Ok().json(response));
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize)]
+struct VirtualRequest {
+	user_id: u64,
+	/// now if not given
+	#[serde(default)]
+	from:    Option<common::Timestamp>,
+}
+
+fn participation_to_response(participation: &contest::Participation) -> serde_json::Value {
+	json!({
+		"id": participation.id,
+		"contest_id": participation.contest_id,
+		"user_id": participation.user_id,
+		"from": participation.from.format(common::TIME_FORMAT).to_string(),
+		"to": participation.to.format(common::TIME_FORMAT).to_string(),
+		"phase": participation.phase(),
+	})
+}
+
+/// replay a finished contest from a start of the user's own
+#[post("/contests/{id}/virtual")]
+fn post_virtual(id: web::Path<u64>, req: web::Json<VirtualRequest>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	let VirtualRequest {
+		user_id,
+		from,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		let contest = contest::get(id).ok_or(not_found(id))?;
+		user::get_list_id()
+			.get(user_id as usize)
+			.ok_or(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("User {} not found.", user_id),
+			}))?;
+		let now = Utc::now();
+		if from.is_some_and(|from| from < now) {
+			return Err(invalid_argument(
+				"Virtual participation starts in the past.".to_string(),
+			));
+		}
+		if contest.phase() != contest::Phase::Finished {
+			return Err(invalid_state(format!("Contest {} is not finished.", id)));
+		}
+		let participation = contest::add_participation(&contest, user_id, from.unwrap_or(now))
+			.ok_or(invalid_state(format!(
+				"User {} already participated virtually in contest {}.",
+				user_id, id
+			)))?;
+		k.resume(HttpResponse::Ok().json(participation_to_response(&participation)));
+		return Ok(());
+	})
+}
+
+#[get("/contests/{id}/virtual/{user_id}")]
+fn get_virtual(path: web::Path<(u64, u64)>) -> KEntrance<HttpResponse> {
+	let (id, user_id) = path.into_inner();
+	callcc_ret(move |k| {
+		let participation =
+			contest::participation(id, user_id).ok_or(participation_not_found(id, user_id))?;
+		k.resume(HttpResponse::Ok().json(participation_to_response(&participation)));
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+enum ScoringRule {
+	#[default]
+	#[serde(rename = "latest")]
+	Latest,
+	/// the earliest of the best submissions
+	#[serde(rename = "highest")]
+	Highest,
+	/// the best score of each subtask across submissions, summed
+	#[serde(rename = "ioi")]
+	Ioi,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+enum TieBreaker {
+	/// the latest of the scoring submissions, earlier ranks higher
+	#[serde(rename = "submission_time")]
+	SubmissionTime,
+	#[serde(rename = "submission_count")]
+	SubmissionCount,
+	#[serde(rename = "user_id")]
+	UserId,
+}
+
+#[derive(Deserialize, Debug)]
+struct RanklistParam {
+	#[serde(default)]
+	scoring_rule: ScoringRule,
+	tie_breaker:  Option<TieBreaker>,
+	/// user asking, admins see through the freeze of ICPC contests and feedback policies
+	viewer:       Option<u64>,
+	/// admin_token of the server, required for admin views
+	token:        Option<String>,
+	/// user whose virtual participation is merged, as of the contest time it reached
+	#[serde(rename = "virtual")]
+	participant:  Option<u64>,
+}
+
+/// contest time of job in the ranklist of contest, None if left out
+///
+/// jobs of virtual participations are left out, but those of the merged one;
+/// the rest then count up to the contest time it reached;
+/// practices are the participations in contest, taken before locking job
+fn ranked_time(
+	contest: &contest::Contest,
+	practices: &[Arc<contest::Participation>],
+	merged: Option<&contest::Participation>,
+	job: &service::Submission,
+) -> Option<common::Timestamp> {
+	if job.raw.contest_id != contest.id {
+		return None;
+	}
+	let practice = practices.iter().find(|participation| {
+		participation.user_id == job.raw.user_id && job.created_time >= participation.from
+	});
+	match (merged, practice) {
+		(None, None) => Some(job.created_time),
+		(None, Some(_)) => None,
+		(Some(merged), None) => (job.raw.user_id != merged.user_id
+			&& job.created_time <= merged.reached(contest))
+		.then_some(job.created_time),
+		(Some(merged), Some(practice)) => {
+			(practice.id == merged.id).then(|| merged.contest_time(contest, job.created_time))
+		}
+	}
+}
+
+/// virtual participations in contest id, see the lock order in contest.rs
+fn practices(id: u64) -> Vec<Arc<contest::Participation>> {
+	contest::get_participations()
+		.iter()
+		.filter(|participation| participation.contest_id == id)
+		.cloned()
+		.collect()
+}
+
+/// entrants of a ranklist and the one each user submits for,
+/// the merged participant enters alone, last unless in the contest
+fn ranked_entrants(
+	contest: &contest::Contest,
+	merged: Option<&contest::Participation>,
+) -> (Vec<contest::Entrant>, HashMap<u64, contest::Entrant>) {
+	let mut entrants = contest.entrants();
+	let mut entrant_of = entrants
+		.iter()
+		.flat_map(|&entrant| {
+			entrant
+				.members()
+				.into_iter()
+				.map(move |user_id| (user_id, entrant))
+		})
+		.collect::<HashMap<_, _>>();
+	if let Some(merged) = merged {
+		let entrant = contest::Entrant::User(merged.user_id);
+		if !entrants.contains(&entrant) {
+			entrants.push(entrant);
+		}
+		entrant_of.insert(merged.user_id, entrant);
+	}
+	return (entrants, entrant_of);
+}
+
+/// "user" or "team" of a ranklist row, named as in the contest
+fn entrant_to_response(
+	contest: Option<&contest::Contest>,
+	entrant: contest::Entrant,
+) -> (&'static str, serde_json::Value) {
+	let name = match contest {
+		Some(contest) => contest.display_name(entrant),
+		None => user::get_list_id()
+			.get(entrant.members()[0] as usize)
+			.map_or(String::new(), |user| user.name.clone()),
+	};
+	match entrant {
+		contest::Entrant::User(user_id) => (
+			"user",
+			json!({
+				"id": user_id,
+				"name": name,
+			}),
+		),
+		contest::Entrant::Team(team_id) => (
+			"team",
+			json!({
+				"id": team_id,
+				"name": name,
+				"user_ids": entrant.members(),
+			}),
+		),
+	}
+}
+
+struct Attempt {
+	entrant:        contest::Entrant,
+	problem_id:     u64,
+	created_time:   common::Timestamp,
+	score:          f64,
+	subtask_scores: Vec<f64>,
+}
+
+/// users ranked by total score, ties share a rank unless broken
+#[get("/contests/{id}/ranklist")]
+fn get_ranklist(
+	id: web::Path<u64>,
+	req: web::Query<RanklistParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	let RanklistParam {
+		scoring_rule,
+		tie_breaker,
+		viewer,
+		token,
+		participant,
+	} = req.into_inner();
+	let admin = config.server.admin(viewer, token.as_deref());
+	callcc_ret(move |k| {
+		// contest 0 holds every problem and user
+		let (problem_ids, phase, contest) = match id {
+			0 => {
+				let mut problem_ids = config
+					.problems
+					.read()
+					.unwrap()
+					.keys()
+					.copied()
+					.collect::<Vec<_>>();
+				problem_ids.sort();
+				(problem_ids, contest::Phase::Running, None)
+			}
+			_ => {
+				let contest = contest::get(id).ok_or(not_found(id))?;
+				(
+					contest.raw.problem_ids.clone(),
+					contest.phase(),
+					Some(contest),
+				)
+			}
+		};
+		let merged = participant
+			.map(|user_id| {
+				contest::participation(id, user_id).ok_or(participation_not_found(id, user_id))
+			})
+			.transpose()?;
+		let (entrants, entrant_of) = match &contest {
+			Some(contest) => ranked_entrants(contest, merged.as_deref()),
+			None => {
+				let users = user::get_list_id().len() as u64;
+				(
+					(0..users).map(contest::Entrant::User).collect(),
+					HashMap::new(),
+				)
+			}
+		};
+		// results of others on problems not giving full feedback stay hidden until it ended
+		let hidden_problems = problem_ids
+			.iter()
+			.copied()
+			.filter(|&problem_id| {
+				!admin
+					&& !contest.as_ref().is_some_and(|contest| contest.ended())
+					&& jobs::setting(&config, problem_id, contest.as_deref()).unwrap_or_default()
+						!= config::Feedback::Full
+			})
+			.collect::<HashSet<_>>();
+		let own = viewer.map(|viewer| {
+			entrant_of
+				.get(&viewer)
+				.copied()
+				.unwrap_or(contest::Entrant::User(viewer))
+		});
+		let hidden = |entrant: contest::Entrant, problem_id: u64| {
+			hidden_problems.contains(&problem_id) && own != Some(entrant)
+		};
+		if let Some(contest) = contest
+			.as_ref()
+			.filter(|contest| contest.raw.icpc.is_some())
+		{
+			k.resume(HttpResponse::Ok().json(icpc_ranklist(
+				contest,
+				admin,
+				&hidden,
+				merged.as_deref(),
+			)));
+			return Ok(());
+		}
+		let weights = problem_ids
+			.iter()
+			.map(|&problem_id| {
+				contest
+					.as_ref()
+					.and_then(|contest| contest.problem(problem_id))
+					.map_or(1.0, |problem| problem.weight)
+			})
+			.collect::<Vec<_>>();
+		// cloned, the hack list is taken for each job, see the lock order in contest.rs
+		let jobs = service::get_list().clone();
+		let practices = practices(id);
+		// pretest results stand until system tests are over, hacked ones score nothing
+		let attempts = jobs
+			.iter()
+			.filter_map(|job| {
+				let hacked = contest.is_some() && contest::hacked(job);
+				let job = job.lock().unwrap();
+				let (entrant, created_time) = match &contest {
+					Some(contest) => (
+						*entrant_of.get(&job.raw.user_id)?,
+						ranked_time(contest, &practices, merged.as_deref(), &job)?,
+					),
+					None => (contest::Entrant::User(job.raw.user_id), job.created_time),
+				};
+				Some(Attempt {
+					entrant,
+					problem_id: job.raw.problem_id,
+					created_time,
+					score: match (phase, job.pretest_score) {
+						(contest::Phase::SystemTesting, Some(score)) => score,
+						(_, None) if hacked => 0.0,
+						_ => job.score,
+					},
+					subtask_scores: match (phase, &job.pretest_scores) {
+						(contest::Phase::SystemTesting, Some(scores)) => scores.clone(),
+						(_, None) if hacked => vec![0.0; job.result_subtasks.len()],
+						_ => job
+							.result_subtasks
+							.iter()
+							.map(|subtask| subtask.score)
+							.collect(),
+					},
+				})
+			})
+			.collect::<Vec<_>>();
+
+		let mut rows = entrants
+			.iter()
+			.map(|&entrant| {
+				let mine = attempts
+					.iter()
+					.filter(|attempt| attempt.entrant == entrant)
+					.collect::<Vec<_>>();
+				// the attempt scoring on each problem, with the score
+				let (chosen, scores): (Vec<_>, Vec<_>) = problem_ids
+					.iter()
+					.map(|&problem_id| {
+						let mut tries = mine
+							.iter()
+							.filter(|attempt| attempt.problem_id == problem_id);
+						let chosen = match scoring_rule {
+							ScoringRule::Latest => tries.next_back().copied(),
+							ScoringRule::Highest => {
+								tries.fold(None, |best: Option<&Attempt>, attempt| match best {
+									Some(best) if best.score >= attempt.score => Some(best),
+									_ => Some(*attempt),
+								})
+							}
+							// the last attempt raising the best score of a subtask
+							ScoringRule::Ioi => {
+								let mut best = Vec::<f64>::new();
+								let mut last = None;
+								for attempt in tries {
+									if best.len() < attempt.subtask_scores.len() {
+										best.resize(attempt.subtask_scores.len(), 0.0);
+									}
+									for (best, &score) in
+										best.iter_mut().zip(&attempt.subtask_scores)
+									{
+										if score > *best {
+											*best = score;
+											last = Some(*attempt);
+										}
+									}
+								}
+								return (last, best.iter().sum::<f64>());
+							}
+						};
+						(chosen, chosen.map_or(0.0, |attempt| attempt.score))
+					})
+					.unzip();
+				let scores = scores
+					.iter()
+					.zip(&weights)
+					.zip(&problem_ids)
+					.map(
+						|((score, weight), &problem_id)| match hidden(entrant, problem_id) {
+							true => 0.0,
+							false => score * weight,
+						},
+					)
+					.collect::<Vec<_>>();
+				// smaller is better, users without submissions last
+				let tie = match tie_breaker {
+					None => (false, 0),
+					Some(TieBreaker::SubmissionTime) => chosen
+						.iter()
+						.flatten()
+						.map(|attempt| attempt.created_time)
+						.max()
+						.map_or((true, 0), |time| (false, time.timestamp_micros())),
+					Some(TieBreaker::SubmissionCount) => (false, mine.len() as i64),
+					// the least member id for teams
+					Some(TieBreaker::UserId) => (
+						false,
+						entrant.members().into_iter().min().unwrap_or(0) as i64,
+					),
+				};
+				// nothing from hacks in a virtual participation
+				let hack_score = match &contest {
+					Some(_)
+						if merged.as_ref().is_some_and(|merged| {
+							contest::Entrant::User(merged.user_id) == entrant
+						}) =>
+					{
+						0.0
+					}
+					Some(contest) => contest.hack_score(entrant),
+					None => 0.0,
+				};
+				(
+					entrant,
+					scores.iter().sum::<f64>() + hack_score,
+					tie,
+					scores,
+					hack_score,
+				)
+			})
+			.collect::<Vec<_>>();
+		rows.sort_by(|lhs, rhs| {
+			rhs.1
+				.total_cmp(&lhs.1)
+				.then(lhs.2.cmp(&rhs.2))
+				.then(lhs.0.cmp(&rhs.0))
+		});
+
+		let mut rank = 0;
+		let ranklist = rows
+			.iter()
+			.enumerate()
+			.map(|(index, (entrant, total, tie, scores, hack_score))| {
+				if index == 0 || (rows[index - 1].1, rows[index - 1].2) != (*total, *tie) {
+					rank = index + 1;
+				}
+				let (key, entrant) = entrant_to_response(contest.as_deref(), *entrant);
+				let mut row = json!({
+					"rank": rank,
+					"scores": scores,
+					"hack_score": hack_score,
+				});
+				row[key] = entrant;
+				row
+			})
+			.collect::<Vec<_>>();
+		k.resume(HttpResponse::Ok().json(ranklist));
+		return Ok(());
+	})
+}
+
+/// a try of an ICPC contest, pending while judged or hidden by the freeze
+struct Try {
+	entrant:      contest::Entrant,
+	problem_id:   u64,
+	created_time: common::Timestamp,
+	pending:      bool,
+	result:       judger::Resultat,
+}
+
+#[derive(Default)]
+struct Cell {
+	solved_at: Option<common::Timestamp>,
+	rejected:  u64,
+	pending:   u64,
+}
+
+/// entrants ranked by problems solved, then penalty minutes, ties share a rank
+fn icpc_ranklist(
+	contest: &contest::Contest,
+	admin: bool,
+	hidden: &impl Fn(contest::Entrant, u64) -> bool,
+	merged: Option<&contest::Participation>,
+) -> Vec<serde_json::Value> {
+	let Some(icpc) = &contest.raw.icpc else {
+		return Vec::new();
+	};
+	let frozen_since = contest.frozen_since(admin);
+	let (entrants, entrant_of) = ranked_entrants(contest, merged);
+	let practices = practices(contest.id);
+	let mut tries = service::get_list()
+		.iter()
+		.filter_map(|job| {
+			let job = job.lock().unwrap();
+			let entrant = *entrant_of.get(&job.raw.user_id)?;
+			let created_time = ranked_time(contest, &practices, merged, &job)?;
+			Some(Try {
+				entrant,
+				problem_id: job.raw.problem_id,
+				created_time,
+				pending: matches!(
+					job.state,
+					service::SubmissionState::Queueing | service::SubmissionState::Running
+				) || frozen_since.is_some_and(|since| created_time >= since)
+					|| hidden(entrant, job.raw.problem_id),
+				result: match job.state {
+					service::SubmissionState::Finished => job.result_final,
+					_ => judger::Resultat::SystemError,
+				},
+			})
+		})
+		.collect::<Vec<_>>();
+	tries.sort_by_key(|attempt| attempt.created_time);
+
+	// per (entrant, problem id), tries after the accepted one do not count
+	let mut cells = HashMap::<(contest::Entrant, u64), Cell>::new();
+	for attempt in &tries {
+		let cell = cells
+			.entry((attempt.entrant, attempt.problem_id))
+			.or_default();
+		if cell.solved_at.is_some() {
+			continue;
+		}
+		if attempt.pending {
+			cell.pending += 1;
+		} else if attempt.result == judger::Resultat::Accepted {
+			cell.solved_at = Some(attempt.created_time);
+		} else if icpc.penalized.contains(&attempt.result) {
+			cell.rejected += 1;
+		}
+	}
+	let first_solves = contest
+		.raw
+		.problem_ids
+		.iter()
+		.map(|&problem_id| {
+			entrants
+				.iter()
+				.filter_map(|&entrant| cells.get(&(entrant, problem_id))?.solved_at)
+				.min()
+		})
+		.collect::<Vec<_>>();
+	let minutes = |time: common::Timestamp| (time - contest.raw.from).num_minutes().max(0) as u64;
+
+	let empty = Cell::default();
+	let mut rows = entrants
+		.iter()
+		.map(|&entrant| {
+			let cells = contest
+				.raw
+				.problem_ids
+				.iter()
+				.map(|&problem_id| cells.get(&(entrant, problem_id)).unwrap_or(&empty))
+				.collect::<Vec<_>>();
+			let solved = cells.iter().filter(|cell| cell.solved_at.is_some()).count() as u64;
+			let penalty = cells
+				.iter()
+				.filter_map(|cell| Some(minutes(cell.solved_at?) + icpc.penalty * cell.rejected))
+				.sum::<u64>();
+			(entrant, solved, penalty, cells)
+		})
+		.collect::<Vec<_>>();
+	rows.sort_by(|lhs, rhs| {
+		rhs.1
+			.cmp(&lhs.1)
+			.then(lhs.2.cmp(&rhs.2))
+			.then(lhs.0.cmp(&rhs.0))
+	});
+
+	let mut rank = 0;
+	return rows
+		.iter()
+		.enumerate()
+		.map(|(index, (entrant, solved, penalty, cells))| {
+			if index == 0 || (rows[index - 1].1, rows[index - 1].2) != (*solved, *penalty) {
+				rank = index + 1;
+			}
+			let (key, entrant) = entrant_to_response(Some(contest), *entrant);
+			let mut row = json!({
+				"rank": rank,
+				"solved": solved,
+				"penalty": penalty,
+				"problems": cells.iter().zip(&first_solves).zip(&contest.raw.problems).map(|((cell, first_solve), problem)| json!({
+					"label": &problem.label,
+					"solved": cell.solved_at.is_some(),
+					// minutes since the start
+					"time": cell.solved_at.map(minutes),
+					"tries": cell.rejected + cell.solved_at.is_some() as u64,
+					"pending": cell.pending,
+					"first_solve": cell.solved_at.is_some() && cell.solved_at == *first_solve,
+				})).collect::<Vec<_>>(),
+			});
+			row[key] = entrant;
+			row
+		})
+		.collect();
+}
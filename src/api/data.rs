@@ -0,0 +1,129 @@
+use {
+	crate::{
+		callcc::{callcc_ret, KEntrance},
+		config, judger, response, service,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+};
+
+#[derive(Deserialize, Debug)]
+struct PostParam {
+	/// body is a tar archive, every file in it is stored
+	#[serde(default)]
+	archive:    bool,
+	/// body is an input of this problem, refused unless its validator accepts it
+	problem_id: Option<u64>,
+	/// must be an admin, proven by token
+	viewer:     Option<u64>,
+	/// admin_token of the server
+	token:      Option<String>,
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+fn internal(message: String) -> HttpResponse {
+	HttpResponse::InternalServerError().json(response::Error {
+		code: 6,
+		reason: "ERR_INTERNAL".to_string(),
+		message,
+	})
+}
+
+/// run the validator of the problem on a stored input away from the server thread
+async fn validate(
+	config: &web::Data<config::Config>,
+	problem_id: u64,
+	hash: String,
+) -> Result<(), HttpResponse> {
+	let problem = config
+		.problem(problem_id)
+		.ok_or(HttpResponse::NotFound().json(response::Error {
+			code:    3,
+			reason:  "ERR_NOT_FOUND".to_string(),
+			message: format!("Problem {} not found.", problem_id),
+		}))?;
+	let Some(first) = problem.cases.first() else {
+		return Ok(());
+	};
+	let case = judger::Case {
+		uid: 0,
+		input: hash,
+		..first.clone()
+	};
+	let complaints = web::block(move || service::validate(&problem, &[case]))
+		.await
+		.map_err(|err| internal(err.to_string()))?
+		.map_err(|err| internal(err.to_string()))?;
+	return match complaints.into_iter().next() {
+		None => Ok(()),
+		Some((_, complaint)) => Err(invalid_argument(format!("Invalid input: {}", complaint))),
+	};
+}
+
+/// store raw request body, responds with the hash to refer to it in cases
+#[post("/data")]
+async fn post(
+	body: web::Bytes,
+	req: web::Query<PostParam>,
+	config: web::Data<config::Config>,
+) -> HttpResponse {
+	if !config.server.admin(req.viewer, req.token.as_deref()) {
+		return HttpResponse::Forbidden().json(response::Error {
+			code:    2,
+			reason:  "ERR_INVALID_STATE".to_string(),
+			message: "Data is uploaded by admins only.".to_string(),
+		});
+	}
+	if req.archive {
+		return match req.problem_id {
+			Some(_) => invalid_argument("Archives cannot be validated.".to_string()),
+			None => match config.store.put_archive(&body) {
+				Ok(entries) => HttpResponse::Ok().json(entries),
+				Err(err) => invalid_argument(format!("Ill archive: {}", err)),
+			},
+		};
+	}
+	let entry = match config.store.put(&body) {
+		Ok(entry) => entry,
+		Err(err) => return internal(err.to_string()),
+	};
+	if let Some(problem_id) = req.problem_id {
+		if let Err(response) = validate(&config, problem_id, entry.hash.clone()).await {
+			return response;
+		}
+	}
+	return HttpResponse::Ok().json(entry);
+}
+
+/// raw content by hash, for judge nodes to fetch and cache
+#[get("/data/{hash}")]
+fn get_hash(hash: web::Path<String>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let hash = hash.into_inner();
+	callcc_ret(move |k| {
+		let not_found = || {
+			HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("Data {} not found.", hash),
+			})
+		};
+		let content = config
+			.store
+			.path(&hash)
+			.and_then(|path| std::fs::read(path).ok())
+			.ok_or_else(not_found)?;
+		k.resume(
+			HttpResponse::Ok()
+				.content_type("application/octet-stream")
+				.body(content),
+		);
+		return Ok(());
+	})
+}
@@ -0,0 +1,187 @@
+use {
+	crate::{
+		api::jobs,
+		callcc::{callcc, callcc_ret, KEntrance},
+		common, config, contest, judger, response, service,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+	std::sync::Arc,
+};
+
+/// byte
+const HACK_INPUT_LIMIT: usize = 1 << 20;
+
+#[derive(Deserialize)]
+struct Request {
+	/// the hacker
+	user_id: u64,
+	/// the submission to challenge
+	job_id:  u64,
+	input:   Arc<String>,
+}
+
+#[derive(Deserialize)]
+struct GetParam {
+	contest_id: Option<u64>,
+	user_id:    Option<u64>,
+}
+
+fn hack_to_response(hack: &contest::Hack) -> serde_json::Value {
+	let verdict = hack.verdict();
+	let target_id = hack.target.lock().unwrap().id;
+	let job = hack.job.lock().unwrap();
+	json!({
+		"id": hack.id,
+		"created_time": job.created_time.format(common::TIME_FORMAT).to_string(),
+		"updated_time": job.updated_time.format(common::TIME_FORMAT).to_string(),
+		"contest_id": hack.contest_id,
+		"user_id": hack.hacker_id,
+		"job_id": target_id,
+		"problem_id": job.problem.id,
+		"input": &hack.input,
+		"state": job.state,
+		"verdict": verdict,
+		// what the target did on the input
+		"result": jobs::ResponseCase::from_case(0, &job.result_cases[0]),
+		"message": match (&job.state, &job.hack_output) {
+			(_, Some(judger::HackOutput::Invalid(complaint))) => Some(complaint.clone()),
+			(service::SubmissionState::SystemError(err), _) => Some(err.clone()),
+			_ => None,
+		},
+	})
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+/// challenge an accepted submission of another contestant during the hacking phase
+#[post("/hacks")]
+fn post(req: web::Json<Request>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let Request {
+		user_id,
+		job_id,
+		input,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		let target = service::get_list().get(job_id as usize).cloned().ok_or(
+			HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("Job {} not found.", job_id),
+			}),
+		)?;
+		let hacked = contest::hacked(&target);
+		// copied out, the contest phase locks every job of its system test, the target too
+		let (request, accepted) = {
+			let target = target.lock().unwrap();
+			let request = service::Request {
+				source:     target.source.clone(),
+				language:   target.language.clone(),
+				problem:    target.problem.clone(),
+				submission: target.raw.clone(),
+				mode:       service::Mode::Judge,
+				pretests:   false,
+			};
+			let accepted = target.state == service::SubmissionState::Finished
+				&& target.result_final == judger::Resultat::Accepted;
+			(request, accepted)
+		};
+		let contest = contest::get(request.submission.contest_id).ok_or(invalid_argument(
+			format!("Job {} is not in a contest.", job_id),
+		))?;
+		let Some(entrant) = contest.entrant_of(user_id) else {
+			return Err(invalid_argument(format!(
+				"User {} is not in contest {}.",
+				user_id, contest.id
+			)));
+		};
+		if contest.entrant_of(request.submission.user_id) == Some(entrant) {
+			return Err(invalid_argument(
+				"Users cannot hack themselves or their team.".to_string(),
+			));
+		}
+		if contest.phase() != contest::Phase::Hacking {
+			return Err(invalid_argument(format!(
+				"Contest {} is not in its hacking phase.",
+				contest.id
+			)));
+		}
+		if !accepted || hacked {
+			return Err(invalid_argument(format!(
+				"Job {} is not an accepted submission.",
+				job_id
+			)));
+		}
+		if request.problem.solution.is_none()
+			|| request.problem.cases.is_empty()
+			|| request.language.is_none()
+		{
+			return Err(invalid_argument(format!(
+				"Problem {} cannot be hacked.",
+				request.problem.id
+			)));
+		}
+		if input.len() > HACK_INPUT_LIMIT {
+			return Err(invalid_argument(format!(
+				"Hack input exceeds {} bytes.",
+				HACK_INPUT_LIMIT
+			)));
+		}
+		let request = service::Request {
+			mode: service::Mode::Hack(input),
+			..request
+		};
+
+		jobs::admit(&config, user_id)?;
+
+		let hack = contest::add_hack(request.submission.contest_id, user_id, target, request);
+		k.resume(HttpResponse::Ok().json(hack_to_response(&hack)));
+		return Ok(());
+	})
+}
+
+#[get("/hacks")]
+fn get(req: web::Query<GetParam>) -> KEntrance<HttpResponse> {
+	let GetParam {
+		contest_id,
+		user_id,
+	} = req.into_inner();
+	callcc(move |k| {
+		let hacks = contest::get_hacks().clone();
+		k.resume(
+			HttpResponse::Ok().json(
+				hacks
+					.iter()
+					.filter(|hack| contest_id.is_none_or(|id| id == hack.contest_id))
+					.filter(|hack| user_id.is_none_or(|id| id == hack.hacker_id))
+					.map(|hack| hack_to_response(hack))
+					.collect::<Vec<_>>(),
+			),
+		);
+	})
+}
+
+#[get("/hacks/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let hack =
+			contest::get_hacks()
+				.get(id as usize)
+				.cloned()
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("Hack {} not found.", id),
+				}))?;
+		k.resume(HttpResponse::Ok().json(hack_to_response(&hack)));
+		return Ok(());
+	})
+}
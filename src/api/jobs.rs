@@ -1,7 +1,11 @@
 use {
-	crate::{callcc::*, common, config, judger, response, service, user},
+	crate::{
+		api::contests, callcc::*, common, config, contest, diff, judger, response, service, user,
+	},
 	actix_web::{
-		delete, get, post, put,
+		delete, get,
+		http::header,
+		post, put,
 		web::{self},
 		HttpResponse,
 	},
@@ -14,11 +18,16 @@ use {
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Submission {
+	#[serde(default)]
 	pub source_code: Arc<String>,
+	#[serde(default)]
 	pub language:    String,
 	pub user_id:     u64,
 	pub contest_id:  u64,
 	pub problem_id:  u64,
+	/// one output per case, for output-only problems
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub outputs:     Option<Arc<Vec<String>>>,
 }
 
 #[derive(Serialize, Clone)]
@@ -31,7 +40,7 @@ pub struct ResponseCase {
 }
 
 impl ResponseCase {
-	fn from_case(id: u64, case: &judger::CaseResult) -> Self {
+	pub(crate) fn from_case(id: u64, case: &judger::CaseResult) -> Self {
 		let null = |result| Self {
 			id,
 			result,
@@ -54,25 +63,211 @@ impl ResponseCase {
 	}
 }
 
-fn submission_to_response(result: service::SubmissionRef) -> serde_json::Value {
-	let result = result.lock().unwrap();
-	let cases = std::iter::once(&result.result_compile)
-		.chain(result.result_cases.iter())
+#[derive(Serialize, Clone)]
+pub struct ResponseSubtask {
+	pub id:     u64,
+	pub name:   String,
+	pub result: judger::Resultat,
+	pub score:  f64,
+}
+
+#[derive(Deserialize, Debug)]
+struct ViewParam {
+	/// user asking, admins see full detail whatever the problem allows
+	viewer: Option<u64>,
+	/// admin_token of the server, required for admin views
+	token:  Option<String>,
+}
+
+impl ViewParam {
+	fn viewer(&self, config: &config::Config) -> Viewer {
+		Viewer {
+			user_id: self.viewer,
+			admin:   config.server.admin(self.viewer, self.token.as_deref()),
+		}
+	}
+}
+
+/// who a job response is for
+#[derive(Clone, Copy, Debug)]
+struct Viewer {
+	user_id: Option<u64>,
+	/// proven by the admin token
+	admin:   bool,
+}
+
+/// whether job is a try after the freeze of contest, hidden from all but its owner until unfrozen
+fn frozen(job: &service::Submission, contest: Option<&contest::Contest>, viewer: Viewer) -> bool {
+	viewer.user_id != Some(job.raw.user_id)
+		&& contest
+			.and_then(|contest| contest.frozen_since(viewer.admin))
+			.is_some_and(|since| job.created_time >= since)
+}
+
+/// feedback on job in contest for viewer, the current setting applies to earlier versions too
+///
+/// the setting of the contest replaces that of the problem, hidden results show once it ended
+fn feedback(
+	config: &config::Config,
+	job: &service::Submission,
+	contest: Option<&contest::Contest>,
+	viewer: Viewer,
+) -> config::Feedback {
+	if frozen(job, contest, viewer) {
+		return config::Feedback::None;
+	}
+	if viewer.admin {
+		return config::Feedback::Full;
+	}
+	let feedback = setting(config, job.problem.id, contest).unwrap_or(job.problem.feedback);
+	return match feedback {
+		config::Feedback::None if contest.is_some_and(|contest| contest.ended()) => {
+			config::Feedback::Subtasks
+		}
+		feedback => feedback,
+	};
+}
+
+/// feedback set on problem_id in contest for all but admins, None if the problem is gone
+pub(crate) fn setting(
+	config: &config::Config,
+	problem_id: u64,
+	contest: Option<&contest::Contest>,
+) -> Option<config::Feedback> {
+	contest
+		.and_then(|contest| contest.raw.feedback)
+		.or_else(|| config.problem(problem_id).map(|problem| problem.feedback))
+}
+
+/// cases of job shown with feedback, compilation aside
+fn shown_cases(job: &service::Submission, feedback: config::Feedback) -> Vec<ResponseCase> {
+	let verdict_only = |case: ResponseCase| ResponseCase {
+		time: 0,
+		memory: 0,
+		info: String::new(),
+		..case
+	};
+	let failed = |result: judger::Resultat| {
+		!matches!(
+			result,
+			judger::Resultat::Waiting
+				| judger::Resultat::Running
+				| judger::Resultat::Skipped
+				| judger::Resultat::Accepted
+		)
+	};
+	let mut failure_shown = false;
+	job.result_cases
+		.iter()
+		.zip(job.problem.cases.iter())
 		.enumerate()
-		.map(|(id, case)| ResponseCase::from_case(id as u64, case))
+		.filter_map(|(id, (case_result, case))| {
+			let response = ResponseCase::from_case(id as u64 + 1, case_result);
+			match feedback {
+				// samples are public anyway
+				_ if case.sample => Some(response),
+				config::Feedback::Full => Some(response),
+				config::Feedback::Verdict => Some(verdict_only(response)),
+				config::Feedback::FirstFailure if !failure_shown && failed(response.result) => {
+					failure_shown = true;
+					Some(verdict_only(response))
+				}
+				_ => None,
+			}
+		})
+		.collect()
+}
+
+fn submission_to_response(
+	result: service::SubmissionRef,
+	config: &config::Config,
+	viewer: Viewer,
+) -> serde_json::Value {
+	let queue_position = service::queue_position(&result);
+	// looked up before locking the job, see the lock order in contest.rs
+	let contest = contest::get(result.lock().unwrap().raw.contest_id);
+	let result = result.lock().unwrap();
+	let feedback = feedback(config, &result, contest.as_deref(), viewer);
+	// compilation is the contestant's own, always shown
+	let cases = std::iter::once(ResponseCase::from_case(0, &result.result_compile))
+		.chain(shown_cases(&result, feedback))
 		.collect::<Vec<_>>();
+	// 1-based as in config
+	let subtasks = match feedback {
+		config::Feedback::None => Vec::new(),
+		_ => result
+			.problem
+			.subtasks
+			.iter()
+			.zip(result.result_subtasks.iter())
+			.enumerate()
+			.map(|(id, (subtask, subtask_result))| ResponseSubtask {
+				id:     id as u64 + 1,
+				name:   subtask.name.clone(),
+				result: subtask_result.result,
+				score:  subtask_result.score,
+			})
+			.collect::<Vec<_>>(),
+	};
+	let hidden = feedback == config::Feedback::None;
 	json!({
 		"id": result.id,
 		"created_time": result.created_time.format(common::TIME_FORMAT).to_string(),
 		"updated_time": result.updated_time.format(common::TIME_FORMAT).to_string(),
 		"submission": result.raw.as_ref(),
+		"problem_version": result.problem.version,
+		"fingerprint": &result.problem.fingerprint,
+		// the entry of the problem in the contest of the job
+		"contest_problem": contest.as_ref().and_then(|contest| {
+			contest.problem(result.raw.problem_id).map(|problem| json!({
+				"label": &problem.label,
+				"name": &problem.name,
+			}))
+		}),
 		"state": result.state,
-		"result":result.result_final,
-		"score": result.score,
+		"queue_position": queue_position,
+		"feedback": feedback,
+		// the result is hidden by the ranklist freeze
+		"frozen": frozen(&result, contest.as_deref(), viewer),
+		"result": (!hidden).then_some(result.result_final),
+		"score": (!hidden).then_some(result.score),
 		"cases": cases,
+		"subtasks": subtasks,
 	})
 }
 
+/// seconds, a full queue gives no better estimate
+const QUEUE_FULL_RETRY_AFTER: u64 = 5;
+
+/// refuse new work if the queue is full or the user submits too often
+pub(crate) fn admit(config: &config::Config, user_id: u64) -> Result<(), HttpResponse> {
+	if config
+		.server
+		.queue_capacity
+		.is_some_and(|capacity| service::queue_len() >= capacity)
+	{
+		return Err(HttpResponse::ServiceUnavailable()
+			.insert_header((header::RETRY_AFTER, QUEUE_FULL_RETRY_AFTER))
+			.json(response::Error {
+				code:    4,
+				reason:  "ERR_RATE_LIMIT".to_string(),
+				message: "Judge queue is full.".to_string(),
+			}));
+	}
+	if let Some(limiter) = &config.limiter {
+		limiter.acquire(user_id).map_err(|wait| {
+			HttpResponse::TooManyRequests()
+				.insert_header((header::RETRY_AFTER, wait.as_secs_f64().ceil() as u64))
+				.json(response::Error {
+					code:    4,
+					reason:  "ERR_RATE_LIMIT".to_string(),
+					message: format!("User {} submits too often.", user_id),
+				})
+		})?;
+	}
+	return Ok(());
+}
+
 #[post("/jobs")]
 fn post(req: web::Json<Submission>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
 	let submission = Arc::new(req.into_inner());
@@ -85,45 +280,129 @@ fn post(req: web::Json<Submission>, config: web::Data<config::Config>) -> KEntra
 				message: format!("User {:?} not found", &submission.user_id),
 			}))?;
 
-		let language = config
-			.languages
-			.get(&submission.language)
-			.ok_or(HttpResponse::NotFound().json(response::Error {
-				code:    3,
-				reason:  "ERR_NOT_FOUND".to_string(),
-				message: format!("language {:?} not found", &submission.language),
-			}))?
-			.clone();
+		let problem =
+			config
+				.problem(submission.problem_id)
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("problem {:?} not found", &submission.problem_id),
+				}))?;
+		if let Some(reason) = problem.unverified.get() {
+			return Err(HttpResponse::BadRequest().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("problem {} failed verification: {}", problem.id, reason),
+			}));
+		}
 
-		let problem = config
-			.problems
-			.get(&submission.problem_id)
-			.ok_or(HttpResponse::NotFound().json(response::Error {
-				code:    3,
-				reason:  "ERR_NOT_FOUND".to_string(),
-				message: format!("problem {:?} not found", &submission.problem_id),
-			}))?
-			.clone();
+		// output-only problems judge uploaded outputs, no language involved
+		let language = match problem.output_only {
+			true => {
+				// one output per case
+				let count = submission.outputs.as_ref().map(|outputs| outputs.len());
+				if count != Some(problem.cases.len()) {
+					return Err(HttpResponse::BadRequest().json(response::Error {
+						code:    1,
+						reason:  "ERR_INVALID_ARGUMENT".to_string(),
+						message: format!(
+							"problem {} requires {} outputs",
+							problem.id,
+							problem.cases.len()
+						),
+					}));
+				}
+				None
+			}
+			false => Some(
+				config
+					.languages
+					.get(&submission.language)
+					.ok_or(HttpResponse::NotFound().json(response::Error {
+						code:    3,
+						reason:  "ERR_NOT_FOUND".to_string(),
+						message: format!("language {:?} not found", &submission.language),
+					}))?
+					.clone(),
+			),
+		};
 
+		let pretests = contests::check_submission(&submission)?;
+		admit(&config, submission.user_id)?;
+
+		// the submitter sees its own tries through the freeze
+		let viewer = Viewer {
+			user_id: Some(submission.user_id),
+			admin:   false,
+		};
 		let request = service::Request {
 			source: submission.source_code.clone(),
 			language,
-			problem,
+			problem: contest::judged(submission.contest_id, problem),
 			submission,
+			mode: service::Mode::Judge,
+			pretests,
 			// cases:   problem.cases.clone(),
 		};
 
 		let result = service::new_job(request);
-		k.resume(HttpResponse::Ok().json(submission_to_response(result)));
+		k.resume(HttpResponse::Ok().json(submission_to_response(result, &config, viewer)));
 
 		return Ok(());
 	})
 }
 
+/// problem version to rejudge against
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Version {
+	Same,
+	Latest,
+	Number(u64),
+}
+
+impl Version {
+	/// "same" (default), "latest" or a version number
+	pub(crate) fn parse(version: Option<&str>) -> Option<Self> {
+		match version {
+			None | Some("same") => Some(Self::Same),
+			Some("latest") => Some(Self::Latest),
+			Some(version) => version.parse().ok().map(Self::Number),
+		}
+	}
+
+	/// None if it does not exist (any more), as judged in contest contest_id
+	pub(crate) fn resolve(
+		self,
+		config: &config::Config,
+		current: &Arc<config::Problem>,
+		contest_id: u64,
+	) -> Option<Arc<config::Problem>> {
+		let problem = match self {
+			Self::Same => return Some(current.clone()),
+			Self::Latest => config.problem(current.id),
+			Self::Number(version) => config.problem_version(current.id, version),
+		};
+		return problem.map(|problem| contest::judged(contest_id, problem));
+	}
+}
+
+#[derive(Deserialize, Debug)]
+struct PutParam {
+	/// problem version to rejudge against: "same" (default), "latest" or a number
+	version: Option<String>,
+	viewer:  Option<u64>,
+	token:   Option<String>,
+}
+
 #[put("/jobs/{id}")]
-fn put_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+fn put_id(
+	id: web::Path<u64>,
+	req: web::Query<PutParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
 	return callcc(move |k: KEntrance<HttpResponse>| {
-		let list = service::get_list();
+		// not held while resolving the contest, see the lock order in contest.rs
+		let list = service::get_list().clone();
 		let id = id.into_inner() as usize;
 		cond! {
 			id >= list.len() => k.resume(HttpResponse::NotFound().json(response::Error {
@@ -139,10 +418,45 @@ fn put_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
 				}));
 			},
 			_ => {
-				let submission = service::rerun_job(list[id].clone());
-				k.resume(HttpResponse::Ok().json(
-					submission_to_response(submission)
-				));
+				let (current, contest_id) = {
+					let job = list[id].lock().unwrap();
+					(job.problem.clone(), job.raw.contest_id)
+				};
+				let version = Version::parse(req.version.as_deref());
+				match version.map(|version| version.resolve(&config, &current, contest_id)) {
+					None => k.resume(HttpResponse::BadRequest().json(response::Error {
+						code: 1,
+						reason: "ERR_INVALID_ARGUMENT".to_string(),
+						message: format!("Invalid version {:?}.", req.version),
+					})),
+					Some(None) => k.resume(HttpResponse::NotFound().json(response::Error {
+						code: 3,
+						reason: "ERR_NOT_FOUND".to_string(),
+						message: format!("Problem {} version {:?} not found.", current.id, req.version),
+					})),
+					// sources cannot be judged as outputs and vice versa
+					Some(Some(problem)) if problem.output_only != current.output_only => {
+						k.resume(HttpResponse::BadRequest().json(response::Error {
+							code: 2,
+							reason: "ERR_INVALID_STATE".to_string(),
+							message: format!("Job {} does not fit problem version {}.", id, problem.version),
+						}));
+					},
+					Some(Some(problem)) => {
+						let submission =
+							service::rerun_job(list[id].clone(), problem, service::Priority::Normal);
+						k.resume(HttpResponse::Ok().json(
+							submission_to_response(
+								submission,
+								&config,
+								Viewer {
+									user_id: req.viewer,
+									admin:   config.server.admin(req.viewer, req.token.as_deref()),
+								},
+							)
+						));
+					},
+				}
 			},
 		}
 	});
@@ -174,23 +488,107 @@ fn delete_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
 }
 
 #[get("/jobs/{id}")]
-fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+fn get_id(
+	id: web::Path<u64>,
+	req: web::Query<ViewParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
 	return callcc(move |k: KEntrance<HttpResponse>| {
-		let list = service::get_list();
 		let id = id.into_inner() as usize;
-		cond! {
-			id >= list.len() => k.resume(HttpResponse::NotFound().json(response::Error {
-				code: 3,
-				reason: "ERR_NOT_FOUND".to_string(),
+		// out of the list before resolving its contest, see the lock order in contest.rs
+		let job = service::get_list().get(id).cloned();
+		match job {
+			None => k.resume(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
 				message: format!("Job {} not found.", id),
 			})),
-			_ => k.resume(HttpResponse::Ok().json(
-				submission_to_response(list[id].clone())
-			)),
+			Some(job) => k.resume(HttpResponse::Ok().json(submission_to_response(
+				job,
+				&config,
+				req.viewer(&config),
+			))),
 		}
 	});
 }
 
+/// differing lines reported for a failed case
+const DIFF_LIMIT: usize = 50;
+
+/// what a failed case printed against the answer, if the problem shows outputs
+#[get("/jobs/{id}/cases/{case}")]
+fn get_case(
+	path: web::Path<(u64, u64)>,
+	req: web::Query<ViewParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let (id, case) = path.into_inner();
+	callcc_ret(move |k| {
+		let job =
+			service::get_list()
+				.get(id as usize)
+				.cloned()
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("Job {} not found.", id),
+				}))?;
+		let contest = contest::get(job.lock().unwrap().raw.contest_id);
+		let job = job.lock().unwrap();
+		// 1-based as in the job response, 0 is compilation
+		let index = (case as usize).wrapping_sub(1);
+		let result = job
+			.result_cases
+			.get(index)
+			.ok_or(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("Case {} of job {} not found.", case, id),
+			}))?;
+		let viewer = req.viewer(&config);
+		let feedback = feedback(&config, &job, contest.as_deref(), viewer);
+		if !shown_cases(&job, feedback)
+			.iter()
+			.any(|shown| shown.id == case)
+		{
+			return Err(HttpResponse::Forbidden().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("Case {} of job {} is hidden.", case, id),
+			}));
+		}
+		// the current setting applies to earlier versions too
+		let show_output = viewer.admin
+			|| job.problem.cases[index].sample
+			|| config
+				.problem(job.problem.id)
+				.map_or(job.problem.show_output, |problem| problem.show_output);
+		if !show_output {
+			return Err(HttpResponse::Forbidden().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("Outputs of problem {} are hidden.", job.problem.id),
+			}));
+		}
+		let output = job.result_outputs[index]
+			.as_ref()
+			.ok_or(HttpResponse::BadRequest().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: format!("Case {} of job {} has not failed.", case, id),
+			}))?;
+		k.resume(HttpResponse::Ok().json(json!({
+			"id": case,
+			"result": ResponseCase::from_case(case, result).result,
+			"output": &output.output,
+			"stderr": &output.stderr,
+			"answer": &output.answer,
+			"diff": diff::diff(&output.output, &output.answer, DIFF_LIMIT),
+		})));
+		return Ok(());
+	})
+}
+
 // adapted from: https://serde.rs/custom-date-format.html
 fn deserialize_option_time<'de, D>(deserializer: D) -> Result<Option<common::Timestamp>, D::Error>
 where
@@ -206,7 +604,9 @@ where
 }
 
 #[derive(Deserialize, Debug)]
-struct GetParam {
+pub(crate) struct GetParam {
+	user_id:    Option<u64>,
+	contest_id: Option<u64>,
 	problem_id: Option<u64>,
 	language:   Option<String>,
 	#[serde(default, deserialize_with = "deserialize_option_time")]
@@ -216,33 +616,53 @@ struct GetParam {
 	result:     Option<judger::Resultat>,
 }
 
-#[get("/jobs")]
-fn get(req: web::Query<GetParam>) -> KEntrance<HttpResponse> {
+/// jobs matching all given conditions of req
+pub(crate) fn filter(req: GetParam) -> impl Fn(&service::SubmissionRef) -> bool {
 	fn option_filter<T>(f: impl Fn(&T, &T) -> bool, a: &Option<T>, b: &T) -> bool {
 		a.as_ref().map_or(true, |a| f(a, b))
 	}
 	fn equal<T: PartialEq>(a: &T, b: &T) -> bool {
 		a == b
 	}
-	let req = req.into_inner();
-	let filter = move |submission: &service::SubmissionRef| -> bool {
+	move |submission: &service::SubmissionRef| -> bool {
 		let submission = submission.lock().unwrap();
-		option_filter(equal, &req.problem_id, &submission.problem.id)
-			&& option_filter(equal, &req.language, &submission.language.name)
+		option_filter(equal, &req.user_id, &submission.raw.user_id)
+			&& option_filter(equal, &req.contest_id, &submission.raw.contest_id)
+			&& option_filter(equal, &req.problem_id, &submission.problem.id)
+			&& option_filter(equal, &req.language, &submission.raw.language)
 			&& option_filter(equal, &req.state, &submission.state)
 			&& option_filter(equal, &req.result, &submission.result_final)
 			&& option_filter(|a, b| a <= b, &req.from, &submission.created_time)
 			&& option_filter(|a, b| a >= b, &req.to, &submission.created_time)
-	};
+	}
+}
 
+#[get("/jobs")]
+fn get(
+	req: web::Query<GetParam>,
+	view: web::Query<ViewParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let mut req = req.into_inner();
+	// matched against the result shown to the viewer, not the hidden one
+	let result = req.result.take();
+	let filter = filter(req);
+	let viewer = view.viewer(&config);
 	return callcc(move |k: KEntrance<HttpResponse>| {
+		let jobs = service::get_list()
+			.iter()
+			.cloned()
+			.filter(filter)
+			.collect::<Vec<_>>();
 		k.resume(
 			HttpResponse::Ok().json(
-				service::get_list()
-					.iter()
-					.cloned()
-					.filter(filter)
-					.map(submission_to_response)
+				jobs.into_iter()
+					.map(|job| submission_to_response(job, &config, viewer))
+					.filter(|response| {
+						result
+							.as_ref()
+							.is_none_or(|result| response["result"] == json!(result))
+					})
 					.collect::<Vec<_>>(),
 			),
 		);
@@ -0,0 +1,284 @@
+use {
+	crate::{
+		callcc::{callcc, callcc_ret, KEntrance},
+		config, response, verify,
+	},
+	actix_web::{delete, get, post, put, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+};
+
+fn problem_to_response(problem: &config::Problem) -> serde_json::Value {
+	json!({
+		"id": problem.id,
+		"version": problem.version,
+		"fingerprint": &problem.fingerprint,
+		"name": &problem.name,
+		"output_only": problem.output_only,
+		"file_io": &problem.file_io,
+		"samples_first": problem.samples_first,
+		"show_output": problem.show_output,
+		"feedback": problem.feedback,
+		"verification": problem.verification.get(),
+		"unverified": problem.unverified.get(),
+		// 1-based as in config
+		"cases": problem.cases.iter().map(|case| json!({
+			"id": case.uid + 1,
+			"score": case.score,
+			"time_limit": case.time_limit,
+			"memory_limit": case.memory_limit,
+			"stack_limit": case.stack_limit,
+			"sample": case.sample,
+		})).collect::<Vec<_>>(),
+		"subtasks": problem.subtasks.iter().enumerate().map(|(id, subtask)| json!({
+			"id": id + 1,
+			"name": &subtask.name,
+			"policy": subtask.policy,
+			"score": subtask.score,
+			"cases": subtask.cases.iter().map(|uid| uid + 1).collect::<Vec<_>>(),
+			"dependency": subtask.dependency.iter().map(|dep| dep + 1).collect::<Vec<_>>(),
+		})).collect::<Vec<_>>(),
+	})
+}
+
+fn not_found(id: u64) -> HttpResponse {
+	HttpResponse::NotFound().json(response::Error {
+		code:    3,
+		reason:  "ERR_NOT_FOUND".to_string(),
+		message: format!("Problem {} not found.", id),
+	})
+}
+
+#[derive(Deserialize, Debug)]
+struct AdminParam {
+	/// must be an admin, proven by token
+	viewer: Option<u64>,
+	/// admin_token of the server
+	token:  Option<String>,
+}
+
+/// refuse anyone but admins
+fn admin(config: &config::Config, req: &AdminParam) -> Result<(), HttpResponse> {
+	if config.server.admin(req.viewer, req.token.as_deref()) {
+		return Ok(());
+	}
+	return Err(HttpResponse::Forbidden().json(response::Error {
+		code:    2,
+		reason:  "ERR_INVALID_STATE".to_string(),
+		message: "Problems are managed by admins only.".to_string(),
+	}));
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+/// load and verify a problem sent over the API away from the server thread,
+/// judging its solutions takes a while
+async fn load(
+	config: &web::Data<config::Config>,
+	raw: config::RawProblem,
+) -> Result<config::Problem, HttpResponse> {
+	let config = config.clone();
+	web::block(move || {
+		raw.stored(&config.store)
+			.and_then(|raw| config.load_problem(raw))
+			.and_then(verify::verified)
+	})
+	.await
+	.map_err(|err| {
+		HttpResponse::InternalServerError().json(response::Error {
+			code:    6,
+			reason:  "ERR_INTERNAL".to_string(),
+			message: err.to_string(),
+		})
+	})?
+	.map_err(|err| invalid_argument(err.to_string()))
+}
+
+/// create a problem, same format as a config entry
+#[post("/problems")]
+async fn post(
+	req: web::Json<config::RawProblem>,
+	admin_req: web::Query<AdminParam>,
+	config: web::Data<config::Config>,
+) -> HttpResponse {
+	if let Err(response) = admin(&config, &admin_req) {
+		return response;
+	}
+	let raw = req.into_inner();
+	let id = raw.id;
+	if config.problem(id).is_some() {
+		return invalid_argument(format!("Problem {} already exists.", id));
+	}
+	let problem = match load(&config, raw).await {
+		Ok(problem) => problem,
+		Err(response) => return response,
+	};
+	return match config.publish(problem, false) {
+		Some(problem) => HttpResponse::Ok().json(problem_to_response(&problem)),
+		None => invalid_argument(format!("Problem {} already exists.", id)),
+	};
+}
+
+/// replace a problem with a new version, queued and running jobs keep the old one
+#[put("/problems/{id}")]
+async fn put_id(
+	id: web::Path<u64>,
+	req: web::Json<config::RawProblem>,
+	admin_req: web::Query<AdminParam>,
+	config: web::Data<config::Config>,
+) -> HttpResponse {
+	if let Err(response) = admin(&config, &admin_req) {
+		return response;
+	}
+	let id = id.into_inner();
+	let raw = config::RawProblem {
+		id,
+		..req.into_inner()
+	};
+	if config.problem(id).is_none() {
+		return not_found(id);
+	}
+	let problem = match load(&config, raw).await {
+		Ok(problem) => problem,
+		Err(response) => return response,
+	};
+	return match config.publish(problem, true) {
+		Some(problem) => HttpResponse::Ok().json(problem_to_response(&problem)),
+		None => not_found(id),
+	};
+}
+
+/// data and history of the removed problem are kept for the jobs still holding it
+#[delete("/problems/{id}")]
+fn delete_id(
+	id: web::Path<u64>,
+	admin_req: web::Query<AdminParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		admin(&config, &admin_req)?;
+		config
+			.problems
+			.write()
+			.unwrap()
+			.remove(&id)
+			.ok_or(not_found(id))?;
+		k.resume(HttpResponse::Ok().body(""));
+		return Ok(());
+	})
+}
+
+#[derive(Deserialize, Debug)]
+struct GetParam {
+	/// a historical version instead of the current one
+	version: Option<u64>,
+}
+
+#[get("/problems/{id}")]
+fn get_id(
+	id: web::Path<u64>,
+	req: web::Query<GetParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let problem = match req.version {
+			None => config.problem(id),
+			Some(version) => config.problem_version(id, version),
+		}
+		.ok_or(not_found(id))?;
+		k.resume(HttpResponse::Ok().json(problem_to_response(&problem)));
+		return Ok(());
+	})
+}
+
+/// every published version, oldest first
+#[get("/problems/{id}/versions")]
+fn get_versions(id: web::Path<u64>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let history = config.problem_history(id);
+		if history.is_empty() {
+			return Err(not_found(id));
+		}
+		k.resume(
+			HttpResponse::Ok().json(
+				history
+					.iter()
+					.map(|problem| {
+						json!({
+							"version": problem.version,
+							"fingerprint": &problem.fingerprint,
+							"name": &problem.name,
+						})
+					})
+					.collect::<Vec<_>>(),
+			),
+		);
+		return Ok(());
+	})
+}
+
+/// input and answer of every sample case
+#[get("/problems/{id}/samples")]
+fn get_samples(id: web::Path<u64>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let problem = config.problem(id).ok_or(not_found(id))?;
+		let read = |hash: &String| -> Result<String, HttpResponse> {
+			let data = config
+				.store
+				.path(hash)
+				.and_then(|path| std::fs::read(path).ok())
+				.ok_or(HttpResponse::InternalServerError().json(response::Error {
+					code:    6,
+					reason:  "ERR_INTERNAL".to_string(),
+					message: format!("Data {} of problem {} is missing.", hash, id),
+				}))?;
+			return Ok(String::from_utf8_lossy(&data).to_string());
+		};
+		let samples = problem
+			.cases
+			.iter()
+			.filter(|case| case.sample)
+			.map(|case| -> Result<_, HttpResponse> {
+				Ok(json!({
+					"id": case.uid + 1,
+					"input": read(&case.input)?,
+					"answer": read(&case.answer)?,
+				}))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		k.resume(HttpResponse::Ok().json(samples));
+		return Ok(());
+	})
+}
+
+#[get("/problems")]
+fn get(config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	callcc(move |k| {
+		let mut problems = config
+			.problems
+			.read()
+			.unwrap()
+			.values()
+			.cloned()
+			.collect::<Vec<_>>();
+		problems.sort_by_key(|problem| problem.id);
+		k.resume(
+			HttpResponse::Ok().json(
+				problems
+					.iter()
+					.map(|problem| problem_to_response(problem))
+					.collect::<Vec<_>>(),
+			),
+		);
+	})
+}
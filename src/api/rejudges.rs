@@ -0,0 +1,123 @@
+use {
+	crate::{
+		api::jobs,
+		callcc::{callcc_ret, KEntrance},
+		common, config, response, service,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+};
+
+#[derive(Deserialize)]
+struct Request {
+	/// same filters as GET /jobs, only finished jobs are rejudged
+	#[serde(flatten)]
+	filter:  jobs::GetParam,
+	/// "same" (default), "latest" or a number, as in PUT /jobs/{id}
+	version: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AdminParam {
+	/// must be an admin, proven by token
+	viewer: Option<u64>,
+	/// admin_token of the server
+	token:  Option<String>,
+}
+
+fn batch_to_response(batch: &service::Batch) -> serde_json::Value {
+	let mut finished = 0;
+	let mut changed = 0;
+	for (job, before) in batch.jobs.iter() {
+		let job = job.lock().unwrap();
+		match job.state {
+			service::SubmissionState::Queueing | service::SubmissionState::Running => {}
+			_ => finished += 1,
+		}
+		if job.state == service::SubmissionState::Finished && job.result_final != *before {
+			changed += 1;
+		}
+	}
+	json!({
+		"id": batch.id,
+		"created_time": batch.created_time.format(common::TIME_FORMAT).to_string(),
+		"jobs": batch.jobs.iter().map(|(job, _)| job.lock().unwrap().id).collect::<Vec<_>>(),
+		"total": batch.jobs.len(),
+		"finished": finished,
+		"changed": changed,
+	})
+}
+
+/// rejudge every matching job at low priority as one batch
+#[post("/rejudges")]
+fn post(
+	req: web::Json<Request>,
+	admin_req: web::Query<AdminParam>,
+	config: web::Data<config::Config>,
+) -> KEntrance<HttpResponse> {
+	let Request {
+		filter,
+		version,
+	} = req.into_inner();
+	let matches = jobs::filter(filter);
+	callcc_ret(move |k| {
+		if !config
+			.server
+			.admin(admin_req.viewer, admin_req.token.as_deref())
+		{
+			return Err(HttpResponse::Forbidden().json(response::Error {
+				code:    2,
+				reason:  "ERR_INVALID_STATE".to_string(),
+				message: "Rejudges are started by admins only.".to_string(),
+			}));
+		}
+		let version = jobs::Version::parse(version.as_deref()).ok_or(
+			HttpResponse::BadRequest().json(response::Error {
+				code:    1,
+				reason:  "ERR_INVALID_ARGUMENT".to_string(),
+				message: format!("Invalid version {:?}.", version),
+			}),
+		)?;
+		let jobs = service::get_list()
+			.iter()
+			.filter(|job| job.lock().unwrap().state == service::SubmissionState::Finished)
+			.filter(|job| matches(job))
+			.cloned()
+			.collect::<Vec<_>>();
+		// versions are resolved out of the job list, see the lock order in contest.rs
+		// jobs without a fitting version (e.g. deleted problem) are left alone
+		let jobs = jobs
+			.into_iter()
+			.filter_map(|job| {
+				let (current, contest_id) = {
+					let job = job.lock().unwrap();
+					(job.problem.clone(), job.raw.contest_id)
+				};
+				version
+					.resolve(&config, &current, contest_id)
+					.filter(|problem| problem.output_only == current.output_only)
+					.map(|problem| (job, problem))
+			})
+			.collect::<Vec<_>>();
+		let batch = service::rerun_batch(jobs, service::Priority::Rejudge);
+		k.resume(HttpResponse::Ok().json(batch_to_response(&batch)));
+		return Ok(());
+	})
+}
+
+#[get("/rejudges/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let batch = service::get_batches().get(id as usize).cloned().ok_or(
+			HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("Rejudge {} not found.", id),
+			}),
+		)?;
+		k.resume(HttpResponse::Ok().json(batch_to_response(&batch)));
+		return Ok(());
+	})
+}
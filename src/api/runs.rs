@@ -0,0 +1,111 @@
+use {
+	crate::{
+		api::jobs,
+		callcc::{callcc_ret, KEntrance},
+		common, config, response, service, user,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+	std::sync::Arc,
+};
+
+#[derive(Deserialize)]
+struct Request {
+	/// limits, sandbox and file names are taken from the problem
+	#[serde(flatten)]
+	submission: jobs::Submission,
+	#[serde(default)]
+	input:      Arc<String>,
+}
+
+fn run_to_response(run: service::SubmissionRef) -> serde_json::Value {
+	let queue_position = service::queue_position(&run);
+	let run = run.lock().unwrap();
+	json!({
+		"id": run.id,
+		"created_time": run.created_time.format(common::TIME_FORMAT).to_string(),
+		"updated_time": run.updated_time.format(common::TIME_FORMAT).to_string(),
+		"problem_id": run.problem.id,
+		"language": &run.raw.language,
+		"state": run.state,
+		"queue_position": queue_position,
+		"compile": jobs::ResponseCase::from_case(0, &run.result_compile),
+		"result": run.result_final,
+		"output": &run.run_output,
+	})
+}
+
+/// run code on the given input without a verdict, not listed among jobs
+#[post("/runs")]
+fn post(req: web::Json<Request>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let Request {
+		submission,
+		input,
+	} = req.into_inner();
+	let submission = Arc::new(submission);
+	callcc_ret(move |k| {
+		user::get_list_id().get(submission.user_id as usize).ok_or(
+			HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("User {:?} not found", &submission.user_id),
+			}),
+		)?;
+		let problem =
+			config
+				.problem(submission.problem_id)
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("problem {:?} not found", &submission.problem_id),
+				}))?;
+		if problem.output_only || problem.cases.is_empty() {
+			return Err(HttpResponse::BadRequest().json(response::Error {
+				code:    1,
+				reason:  "ERR_INVALID_ARGUMENT".to_string(),
+				message: format!("problem {} has nothing to run", problem.id),
+			}));
+		}
+		let language = config
+			.languages
+			.get(&submission.language)
+			.ok_or(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("language {:?} not found", &submission.language),
+			}))?
+			.clone();
+
+		jobs::admit(&config, submission.user_id)?;
+
+		let run = service::new_run(service::Request {
+			source: submission.source_code.clone(),
+			language: Some(language),
+			problem,
+			submission,
+			mode: service::Mode::Custom(input),
+			pretests: false,
+		});
+		k.resume(HttpResponse::Ok().json(run_to_response(run)));
+		return Ok(());
+	})
+}
+
+#[get("/runs/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let run =
+			service::get_runs()
+				.get(id as usize)
+				.cloned()
+				.ok_or(HttpResponse::NotFound().json(response::Error {
+					code:    3,
+					reason:  "ERR_NOT_FOUND".to_string(),
+					message: format!("Run {} not found.", id),
+				}))?;
+		k.resume(HttpResponse::Ok().json(run_to_response(run)));
+		return Ok(());
+	})
+}
@@ -0,0 +1,186 @@
+use {
+	crate::{
+		api::jobs,
+		callcc::{callcc_ret, KEntrance},
+		common, config, judger, response, service, user,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+	std::sync::Arc,
+};
+
+const ITERATION_LIMIT: u64 = 10000;
+/// us
+const TIME_BUDGET_LIMIT: u64 = 60_000_000;
+
+fn default_iterations() -> u64 {
+	100
+}
+
+fn default_time_budget() -> u64 {
+	10_000_000
+}
+
+fn default_brute_time_limit() -> u64 {
+	10_000_000
+}
+
+#[derive(Deserialize)]
+struct Program {
+	language:    String,
+	source_code: String,
+}
+
+#[derive(Deserialize)]
+struct Request {
+	/// the code to test, limits, sandbox and checker are taken from the problem
+	#[serde(flatten)]
+	submission:       jobs::Submission,
+	/// reads a seed on stdin, prints an input
+	generator:        Program,
+	/// answers the generated inputs
+	brute:            Program,
+	#[serde(default = "default_iterations")]
+	iterations:       u64,
+	/// us
+	#[serde(default = "default_time_budget")]
+	time_budget:      u64,
+	/// us per run of the brute force, the problem limits only bind the code
+	#[serde(default = "default_brute_time_limit")]
+	brute_time_limit: u64,
+}
+
+fn stress_to_response(stress: service::SubmissionRef) -> serde_json::Value {
+	let queue_position = service::queue_position(&stress);
+	let stress = stress.lock().unwrap();
+	let output = stress.stress_output.as_ref();
+	json!({
+		"id": stress.id,
+		"created_time": stress.created_time.format(common::TIME_FORMAT).to_string(),
+		"updated_time": stress.updated_time.format(common::TIME_FORMAT).to_string(),
+		"problem_id": stress.problem.id,
+		"language": &stress.raw.language,
+		"state": stress.state,
+		"queue_position": queue_position,
+		"compile": jobs::ResponseCase::from_case(0, &stress.result_compile),
+		"result": stress.result_final,
+		"iterations": output.map_or(0, |output| output.iterations),
+		// the first generated input the code failed on
+		"counterexample": output.and_then(|output| output.input.as_ref()).map(|input| json!({
+			"input": input,
+			"case": jobs::ResponseCase::from_case(0, &stress.result_cases[0]),
+			"output": &stress.result_outputs[0],
+		})),
+		"message": match &stress.state {
+			service::SubmissionState::SystemError(err) => Some(err),
+			_ => None,
+		},
+	})
+}
+
+fn not_found(message: String) -> HttpResponse {
+	HttpResponse::NotFound().json(response::Error {
+		code: 3,
+		reason: "ERR_NOT_FOUND".to_string(),
+		message,
+	})
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+/// compare code with a brute force on generated inputs until they disagree, not listed among jobs
+#[post("/stress_tests")]
+fn post(req: web::Json<Request>, config: web::Data<config::Config>) -> KEntrance<HttpResponse> {
+	let Request {
+		submission,
+		generator,
+		brute,
+		iterations,
+		time_budget,
+		brute_time_limit,
+	} = req.into_inner();
+	let submission = Arc::new(submission);
+	callcc_ret(move |k| {
+		user::get_list_id()
+			.get(submission.user_id as usize)
+			.ok_or(not_found(format!(
+				"User {:?} not found",
+				&submission.user_id
+			)))?;
+		let problem = config
+			.problem(submission.problem_id)
+			.ok_or(not_found(format!(
+				"problem {:?} not found",
+				&submission.problem_id
+			)))?;
+		if problem.output_only || problem.cases.is_empty() {
+			return Err(invalid_argument(format!(
+				"problem {} has nothing to run",
+				problem.id
+			)));
+		}
+		if !(1..=ITERATION_LIMIT).contains(&iterations)
+			|| !(1..=TIME_BUDGET_LIMIT).contains(&time_budget)
+			|| !(1..=TIME_BUDGET_LIMIT).contains(&brute_time_limit)
+		{
+			return Err(invalid_argument(format!(
+				"iterations must be in 1..={} and time_budget and brute_time_limit in 1..={}",
+				ITERATION_LIMIT, TIME_BUDGET_LIMIT
+			)));
+		}
+		let language = |name: &String| {
+			config
+				.languages
+				.get(name)
+				.cloned()
+				.ok_or(not_found(format!("language {:?} not found", name)))
+		};
+		let code = |program: Program| -> Result<judger::Code, HttpResponse> {
+			Ok(judger::Code {
+				language: language(&program.language)?.as_ref().clone(),
+				source:   program.source_code,
+			})
+		};
+		let stress = judger::Stress {
+			generator: code(generator)?,
+			brute: code(brute)?,
+			iterations,
+			time_budget,
+			brute_time_limit,
+		};
+		let language = language(&submission.language)?;
+
+		jobs::admit(&config, submission.user_id)?;
+
+		let stress = service::new_stress(service::Request {
+			source: submission.source_code.clone(),
+			language: Some(language),
+			problem,
+			submission,
+			mode: service::Mode::Stress(Arc::new(stress)),
+			pretests: false,
+		});
+		k.resume(HttpResponse::Ok().json(stress_to_response(stress)));
+		return Ok(());
+	})
+}
+
+#[get("/stress_tests/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let stress = service::get_stress_tests()
+			.get(id as usize)
+			.cloned()
+			.ok_or(not_found(format!("Stress test {} not found.", id)))?;
+		k.resume(HttpResponse::Ok().json(stress_to_response(stress)));
+		return Ok(());
+	})
+}
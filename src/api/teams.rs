@@ -0,0 +1,86 @@
+use {
+	crate::{
+		callcc::{callcc, callcc_ret, KEntrance},
+		response, team, user,
+	},
+	actix_web::{get, post, web, HttpResponse},
+	serde::Deserialize,
+	serde_json::json,
+	std::collections::HashSet,
+};
+
+#[derive(Deserialize)]
+struct Request {
+	name:     String,
+	user_ids: Vec<u64>,
+}
+
+fn team_to_response(team: &team::Team) -> serde_json::Value {
+	json!({
+		"id": team.id,
+		"name": &team.name,
+		"user_ids": &team.user_ids,
+	})
+}
+
+fn invalid_argument(message: String) -> HttpResponse {
+	HttpResponse::BadRequest().json(response::Error {
+		code: 1,
+		reason: "ERR_INVALID_ARGUMENT".to_string(),
+		message,
+	})
+}
+
+#[post("/teams")]
+fn post(req: web::Json<Request>) -> KEntrance<HttpResponse> {
+	let Request {
+		name,
+		user_ids,
+	} = req.into_inner();
+	callcc_ret(move |k| {
+		if user_ids.is_empty() || user_ids.iter().collect::<HashSet<_>>().len() != user_ids.len() {
+			return Err(invalid_argument(
+				"Team members must be distinct and not empty.".to_string(),
+			));
+		}
+		let users = user::get_list_id().len() as u64;
+		if let Some(&user_id) = user_ids.iter().find(|&&user_id| user_id >= users) {
+			return Err(HttpResponse::NotFound().json(response::Error {
+				code:    3,
+				reason:  "ERR_NOT_FOUND".to_string(),
+				message: format!("User {} not found.", user_id),
+			}));
+		}
+		let team = team::new_team(name, user_ids).map_err(invalid_argument)?;
+		k.resume(HttpResponse::Ok().json(team_to_response(&team)));
+		return Ok(());
+	})
+}
+
+#[get("/teams")]
+fn get() -> KEntrance<HttpResponse> {
+	callcc(move |k| {
+		k.resume(
+			HttpResponse::Ok().json(
+				team::get_list()
+					.iter()
+					.map(|team| team_to_response(team))
+					.collect::<Vec<_>>(),
+			),
+		);
+	})
+}
+
+#[get("/teams/{id}")]
+fn get_id(id: web::Path<u64>) -> KEntrance<HttpResponse> {
+	let id = id.into_inner();
+	callcc_ret(move |k| {
+		let team = team::get(id).ok_or(HttpResponse::NotFound().json(response::Error {
+			code:    3,
+			reason:  "ERR_NOT_FOUND".to_string(),
+			message: format!("Team {} not found.", id),
+		}))?;
+		k.resume(HttpResponse::Ok().json(team_to_response(&team)));
+		return Ok(());
+	})
+}
@@ -1,55 +1,249 @@
 use {
 	super::judger,
-	crate::workaround,
+	crate::{
+		import,
+		limit::{RateLimiter, RawRateLimit},
+		service,
+		store::Store,
+		user, verify, workaround,
+	},
 	serde::{Deserialize, Serialize},
+	sha2::{Digest, Sha256},
 	std::{
-		collections::HashMap,
+		collections::{HashMap, HashSet},
 		fs,
 		io::{Error, Result},
-		sync::Arc,
+		path::PathBuf,
+		sync::{Arc, Mutex, OnceLock, RwLock},
 	},
 };
 
+/// Resource limits, each level falls back to the enclosing one: case, subtask, problem
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct RawLimits {
+	/// us
+	pub time_limit:   Option<u64>,
+	/// byte, 0 for the max configurable
+	pub memory_limit: Option<u64>,
+	/// byte, 0 or absent for the address space limit, 64MiB above the memory limit
+	pub stack_limit:  Option<u64>,
+}
+impl RawLimits {
+	pub fn or(self, other: Self) -> Self {
+		Self {
+			time_limit:   self.time_limit.or(other.time_limit),
+			memory_limit: self.memory_limit.or(other.memory_limit),
+			stack_limit:  self.stack_limit.or(other.stack_limit),
+		}
+	}
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RawCase {
-	pub score:        f64,
-	pub input_file:   String,
-	pub answer_file:  String,
-	pub time_limit:   u64,
-	pub memory_limit: u64,
+	pub score:       f64,
+	#[serde(default)]
+	pub input_file:  String,
+	#[serde(default)]
+	pub answer_file: String,
+	/// data already in the store, instead of input_file
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub input_hash:  Option<String>,
+	/// data already in the store, instead of answer_file
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub answer_hash: Option<String>,
+	/// public, its data can be downloaded and its result is always shown
+	#[serde(default)]
+	pub sample:      bool,
+	/// judged during contests with pretests, the rest only in system tests
+	#[serde(default)]
+	pub pretest:     bool,
+	#[serde(flatten)]
+	pub limits:      RawLimits,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Server {
-	pub bind_address: String,
-	pub bind_port:    u16,
+	pub bind_address:   String,
+	pub bind_port:      u16,
+	/// max queueing submissions, further ones are refused; absent for unbounded
+	#[serde(default)]
+	pub queue_capacity: Option<usize>,
+	/// absent for unlimited
+	#[serde(default)]
+	pub rate_limit:     Option<RawRateLimit>,
+	/// admins pass it as token to see what others cannot, absent to disable such views
+	#[serde(default)]
+	pub admin_token:    Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Server {
+	/// whether viewer is an admin proving it with the admin token
+	pub fn admin(&self, viewer: Option<u64>, token: Option<&str>) -> bool {
+		viewer.is_some_and(user::is_admin)
+			&& self
+				.admin_token
+				.as_deref()
+				.is_some_and(|admin_token| token == Some(admin_token))
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub enum RawProblemType {
+	#[default]
 	#[serde(rename = "standard")]
 	Standard,
 	#[serde(rename = "strict")]
 	Strict,
 	#[serde(rename = "spj")]
 	Checker,
+	/// contestants upload one output per case, checked by special_judge if given
+	#[serde(rename = "output_only")]
+	OutputOnly,
+}
+
+/// how much of a job result is shown to anyone but admins
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Feedback {
+	/// verdict, time, memory and checker message of every case
+	#[default]
+	#[serde(rename = "full")]
+	Full,
+	#[serde(rename = "verdict")]
+	Verdict,
+	/// verdict of the first failed case only
+	#[serde(rename = "first_failure")]
+	FirstFailure,
+	/// subtask results, no cases
+	#[serde(rename = "subtasks")]
+	Subtasks,
+	/// nothing beyond compilation, subtasks once the contest of the job ended
+	#[serde(rename = "none")]
+	None,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+pub struct RawSubtask {
+	pub name:       Option<String>,
+	#[serde(default)]
+	pub policy:     judger::Policy,
+	/// 1-based case indices
+	pub cases:      Vec<u64>,
+	/// defaults to the sum of case scores
+	pub score:      Option<f64>,
+	/// 1-based indices of subtasks which must be accepted first
+	#[serde(default)]
+	pub dependency: Vec<u64>,
+	#[serde(flatten)]
+	pub limits:     RawLimits,
+}
+
+/// program of a problem setter, source_file is read at load time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RawSolution {
+	pub language:    String,
+	#[serde(default)]
+	pub source_file: String,
+	/// code already in the store, instead of source_file
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub source_hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct RawProblemMisc {
-	pub special_judge: Option<Vec<String>>,
-	pub packing:       Option<Vec<Vec<u64>>>,
+	pub special_judge:    Option<Vec<String>>,
+	/// exits with 0 if the input file %INPUT% (also on stdin) satisfies the constraints
+	pub validator:        Option<Vec<String>>,
+	/// official solution, must be accepted on load, its output answers hack inputs
+	pub solution:         Option<RawSolution>,
+	/// too slow or subtly wrong ones, none may be accepted on load
+	#[serde(default)]
+	pub wrong_solutions:  Vec<RawSolution>,
+	/// chains of 1-based case indices, shorthand for all-or-nothing subtasks
+	pub packing:          Option<Vec<Vec<u64>>>,
+	pub subtasks:         Option<Vec<RawSubtask>>,
+	pub file_io:          Option<judger::FileIo>,
+	/// judge samples before other cases, and stop if one fails
+	#[serde(default)]
+	pub samples_first:    bool,
+	/// samples are worth nothing, subtask scores default to the sum of the rest
+	#[serde(default)]
+	pub unscored_samples: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawProblem {
-	pub id:      u64,
-	pub name:    String,
-	#[serde(rename = "type")]
-	pub type_:   RawProblemType,
-	pub misc:    RawProblemMisc,
-	pub cases:   Vec<RawCase>,
-	pub sandbox: Option<bool>,
+	pub id:          u64,
+	/// name, type, misc and cases may be left out when imported from a package
+	#[serde(default)]
+	pub name:        String,
+	#[serde(rename = "type", default)]
+	pub type_:       RawProblemType,
+	#[serde(default)]
+	pub misc:        RawProblemMisc,
+	#[serde(default)]
+	pub cases:       Vec<RawCase>,
+	pub sandbox:     Option<bool>,
+	/// let contestants fetch output and answer of failed hidden cases, samples always can
+	#[serde(default)]
+	pub show_output: bool,
+	#[serde(default)]
+	pub feedback:    Feedback,
+	/// defaults for all cases
+	#[serde(flatten)]
+	pub limits:      RawLimits,
+	/// fields given above take precedence over the package
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub package:     Option<import::RawPackage>,
+}
+
+impl RawProblem {
+	/// the problem as sent over the API, which must not name local files: data and
+	/// programs are referred to by store hash, as are files in special_judge and validator
+	pub fn stored(mut self, store: &Store) -> Result<Self> {
+		if self.package.is_some() {
+			return Err(Error::other(
+				"packages are local files, upload their data instead",
+			));
+		}
+		for (index, case) in self.cases.iter().enumerate() {
+			if !case.input_file.is_empty() || !case.answer_file.is_empty() {
+				return Err(Error::other(format!(
+					"case {} names local files, give input_hash and answer_hash instead",
+					index + 1
+				)));
+			}
+		}
+		let solutions = self
+			.misc
+			.solution
+			.iter()
+			.chain(self.misc.wrong_solutions.iter());
+		for solution in solutions {
+			if !solution.source_file.is_empty() {
+				return Err(Error::other(format!(
+					"solution {} is a local file, give source_hash instead",
+					solution.source_file
+				)));
+			}
+		}
+		// pack() ships every file a command names, so only stored ones may be named
+		let stored = |command: Vec<String>| -> Result<Vec<String>> {
+			command
+				.into_iter()
+				.map(|entry| match store.path(&entry) {
+					Some(path) => Ok(path.to_string_lossy().to_string()),
+					None if std::path::Path::new(&entry).exists() => Err(Error::other(format!(
+						"{} is a local file, refer to stored data by hash instead",
+						entry
+					))),
+					None => Ok(entry),
+				})
+				.collect()
+		};
+		self.misc.special_judge = self.misc.special_judge.map(stored).transpose()?;
+		self.misc.validator = self.misc.validator.map(stored).transpose()?;
+		return Ok(self);
+	}
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -59,51 +253,333 @@ pub struct RawConfig {
 	pub languages: Vec<judger::Language>,
 }
 
+#[derive(Clone)]
 pub struct Problem {
-	pub id:       u64,
-	pub name:     String,
-	pub checker:  workaround::RemoteCommand,
-	pub data_dir: String,
-	pub cases:    Vec<judger::Case>,
-	pub sandbox:  bool,
+	pub id:              u64,
+	/// bumped on every update
+	pub version:         u64,
+	/// hash of everything affecting results: data, limits, subtasks and checker
+	pub fingerprint:     String,
+	pub name:            String,
+	pub checker:         workaround::RemoteCommand,
+	/// the data store, cases refer to files in it by hash
+	pub data_dir:        String,
+	pub cases:           Vec<judger::Case>,
+	pub subtasks:        Vec<judger::Subtask>,
+	pub sandbox:         bool,
+	pub file_io:         judger::FileIo,
+	/// no compile and run, submissions carry outputs instead of source
+	pub output_only:     bool,
+	pub samples_first:   bool,
+	pub show_output:     bool,
+	pub feedback:        Feedback,
+	pub validator:       Option<workaround::RemoteCommand>,
+	/// hacks are only possible with one
+	pub solution:        Option<judger::Code>,
+	pub wrong_solutions: Vec<judger::Code>,
+	/// run of the reference solution, set once judged
+	pub verification:    OnceLock<verify::Verification>,
+	/// why its solutions failed when loaded from the config file, it takes no submissions
+	pub unverified:      OnceLock<String>,
 }
 impl Problem {
-	fn from(data_dir: &std::path::Path, raw: RawProblem) -> Result<Self> {
-		fn parse_packing(
+	/// packages are extracted into data_dir/{id}/{version}, case data goes to the store
+	pub fn load(
+		data_dir: &std::path::Path,
+		store: &Store,
+		languages: &HashMap<String, Arc<judger::Language>>,
+		raw: RawProblem,
+		version: u64,
+	) -> Result<Self> {
+		let problem_dir = data_dir.join(format!("{}/{}", raw.id, version));
+		fs::create_dir_all(&problem_dir)?;
+		let problem = import::resolve(raw, &problem_dir.join("package"))
+			.and_then(|raw| Problem::from(store, languages, raw))
+			.and_then(|problem| problem.validate().map(|_| problem));
+		if problem.is_err() {
+			let _ = fs::remove_dir_all(&problem_dir);
+		}
+		let problem = Self {
+			version,
+			..problem?
+		};
+		return Ok(Self {
+			fingerprint: problem.fingerprint(),
+			..problem
+		});
+	}
+
+	/// run the validator on every case input in a judger, all violations are reported at once
+	fn validate(&self) -> Result<()> {
+		let violations = service::validate(self, &self.cases)
+			.map_err(|err| Error::other(err.to_string()))?
+			.into_iter()
+			.map(|(uid, complaint)| format!("case {}: {}", uid + 1, complaint))
+			.collect::<Vec<_>>();
+		if !violations.is_empty() {
+			return Err(Error::other(format!(
+				"invalid input of {}",
+				violations.join("; ")
+			)));
+		}
+		return Ok(());
+	}
+
+	fn fingerprint(&self) -> String {
+		let judged = serde_json::to_vec(&(
+			&self.cases,
+			&self.subtasks,
+			&self.checker,
+			&self.file_io,
+			self.output_only,
+			self.samples_first,
+		))
+		.unwrap();
+		format!("{:x}", Sha256::digest(judged))
+	}
+
+	fn from(
+		store: &Store,
+		languages: &HashMap<String, Arc<judger::Language>>,
+		raw: RawProblem,
+	) -> Result<Self> {
+		/// returns subtasks, and for each case the limits of the first subtask containing it
+		fn parse_subtasks(
 			packing: Option<Vec<Vec<u64>>>,
-			mut cases: Vec<judger::Case>,
-		) -> Result<Vec<judger::Case>> {
-			match packing {
-				None => {
-					for case in &mut cases {
-						case.pack_score = case.score;
+			subtasks: Option<Vec<RawSubtask>>,
+			cases: &[RawCase],
+		) -> Result<(Vec<judger::Subtask>, Vec<RawLimits>)> {
+			let singleton = |uid: u64| RawSubtask {
+				name:       None,
+				policy:     judger::Policy::Sum,
+				cases:      vec![uid],
+				score:      None,
+				dependency: Vec::new(),
+				limits:     RawLimits::default(),
+			};
+			let explicit = subtasks.is_some();
+			let raw_subtasks = match (packing, subtasks) {
+				(Some(_), Some(_)) => {
+					return Err(Error::other("packing and subtasks are exclusive"));
+				}
+				(None, Some(subtasks)) => subtasks,
+				// each pack is an all-or-nothing subtask, other cases score on their own
+				(Some(packing), None) => {
+					let packed: HashSet<u64> = packing.iter().flatten().copied().collect();
+					packing
+						.into_iter()
+						.map(|pack| RawSubtask {
+							name:       None,
+							policy:     judger::Policy::All,
+							cases:      pack,
+							score:      None,
+							dependency: Vec::new(),
+							limits:     RawLimits::default(),
+						})
+						.chain(
+							(1..=cases.len() as u64)
+								.filter(|uid| !packed.contains(uid))
+								.map(singleton),
+						)
+						.collect()
+				}
+				(None, None) => (1..=cases.len() as u64).map(singleton).collect(),
+			};
+
+			let mut case_limits: Vec<Option<RawLimits>> = cases.iter().map(|_| None).collect();
+			for raw in &raw_subtasks {
+				// out of range indices are reported below
+				for uid in raw.cases.iter().filter_map(|uid| uid.checked_sub(1)) {
+					if let Some(limits @ None) = case_limits.get_mut(uid as usize) {
+						*limits = Some(raw.limits);
 					}
-					Ok(cases)
 				}
-				Some(packing) => {
-					for mut pack in packing {
-						for uid in &mut pack {
-							*uid -= 1;
-						}
-						let &last_uid = pack.last().ok_or(Error::other("ill packing"))?;
-						let mut prev_uid: Option<u64> = None;
-						for uid in pack {
-							cases[last_uid as usize].pack_score += cases[uid as usize].score;
-							if let Some(prev_uid) = prev_uid {
-								cases[uid as usize].dependency.push(prev_uid);
-							}
-							prev_uid = Some(uid);
-						}
+			}
+
+			// indices are 1-based in config
+			let subtask_count = raw_subtasks.len() as u64;
+			let subtasks = raw_subtasks
+				.into_iter()
+				.enumerate()
+				.map(|(index, raw)| -> Result<judger::Subtask> {
+					let ill = |message: String| {
+						Error::other(format!("ill subtask {}: {}", index + 1, message))
+					};
+					if raw.cases.is_empty() {
+						return Err(ill("no case".to_string()));
 					}
-					Ok(cases)
+					let mut seen = HashSet::new();
+					let subtask_cases = raw
+						.cases
+						.iter()
+						.map(|&uid| match uid {
+							1.. if uid <= cases.len() as u64 && seen.insert(uid) => Ok(uid - 1),
+							_ => Err(ill(format!("case {} out of range or repeated", uid))),
+						})
+						.collect::<Result<Vec<u64>>>()?;
+					let dependency = raw
+						.dependency
+						.iter()
+						.map(|&dep| match dep {
+							1.. if dep <= subtask_count && dep != index as u64 + 1 => Ok(dep - 1),
+							_ => Err(ill(format!("dependency {} out of range", dep))),
+						})
+						.collect::<Result<Vec<u64>>>()?;
+					Ok(judger::Subtask {
+						name: raw.name.unwrap_or(format!("Subtask {}", index + 1)),
+						policy: raw.policy,
+						score: raw.score.unwrap_or(
+							subtask_cases
+								.iter()
+								.map(|&uid| cases[uid as usize].score)
+								.sum(),
+						),
+						cases: subtask_cases,
+						dependency,
+					})
+				})
+				.collect::<Result<Vec<_>>>()?;
+
+			if explicit {
+				let covered: HashSet<u64> = subtasks
+					.iter()
+					.flat_map(|subtask| subtask.cases.iter())
+					.copied()
+					.collect();
+				if let Some(uid) = (0..cases.len() as u64).find(|uid| !covered.contains(uid)) {
+					return Err(Error::other(format!("case {} is in no subtask", uid + 1)));
 				}
 			}
+			judger::subtask_order(&subtasks)
+				.ok_or(Error::other("subtask dependencies form a cycle"))?;
+			Ok((
+				subtasks,
+				case_limits
+					.into_iter()
+					.map(Option::unwrap_or_default)
+					.collect(),
+			))
+		}
+
+		/// effective limits of a case, (time, memory, stack)
+		fn parse_limits(uid: usize, limits: RawLimits) -> Result<(u64, u64, u64)> {
+			// max(configurable) memory limit: 2G
+			const MAX_MEMORY: u64 = 2 * 1024 * 1024 * 1024;
+			let ill = |message: &str| {
+				Error::other(format!("ill limits of case {}: {}", uid + 1, message))
+			};
+			let time_limit = match limits.time_limit {
+				None => return Err(ill("no time_limit")),
+				Some(0) => return Err(ill("time_limit is 0")),
+				Some(x) => x,
+			};
+			let memory_limit = match limits.memory_limit.unwrap_or(0) {
+				0 => MAX_MEMORY,
+				x if x > MAX_MEMORY => return Err(ill("memory_limit exceeds 2G")),
+				x => x,
+			};
+			let stack_limit = limits.stack_limit.unwrap_or(0);
+			if stack_limit > memory_limit {
+				return Err(ill("stack_limit exceeds memory_limit"));
+			}
+			Ok((time_limit, memory_limit, stack_limit))
+		}
+
+		fn parse_file_io(file_io: Option<judger::FileIo>) -> Result<judger::FileIo> {
+			let file_io = file_io.unwrap_or_default();
+			// files live in the sandbox working directory, reject anything escaping it
+			for name in [&file_io.input, &file_io.output].into_iter().flatten() {
+				if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+					return Err(Error::other(format!("ill file_io name {:?}", name)));
+				}
+			}
+			Ok(file_io)
+		}
+
+		/// hash of a case file, stored on first sight
+		fn parse_data(
+			store: &Store,
+			uid: usize,
+			hash: Option<String>,
+			file: &str,
+		) -> Result<String> {
+			match hash {
+				Some(hash) => match store.path(&hash) {
+					Some(_) => Ok(hash),
+					None => Err(Error::other(format!(
+						"data {} of case {} not in store",
+						hash,
+						uid + 1
+					))),
+				},
+				None => Ok(store.put_file(std::path::Path::new(file))?.hash),
+			}
+		}
+
+		fn parse_solution(
+			store: &Store,
+			languages: &HashMap<String, Arc<judger::Language>>,
+			solution: RawSolution,
+		) -> Result<judger::Code> {
+			let language = languages
+				.get(&solution.language)
+				.ok_or(Error::other(format!(
+					"unknown language {:?} of solution {}",
+					solution.language, solution.source_file
+				)))?;
+			Ok(judger::Code {
+				language: language.as_ref().clone(),
+				source:   match &solution.source_hash {
+					Some(hash) => fs::read_to_string(
+						store
+							.path(hash)
+							.ok_or(Error::other(format!("solution {} not in store", hash)))?,
+					)?,
+					None => fs::read_to_string(&solution.source_file)?,
+				},
+			})
+		}
+
+		let mut raw = raw;
+		if raw.misc.unscored_samples {
+			for case in raw.cases.iter_mut().filter(|case| case.sample) {
+				case.score = 0.0;
+			}
+		}
+		let (subtasks, subtask_limits) =
+			parse_subtasks(raw.misc.packing, raw.misc.subtasks, &raw.cases)?;
+		let cases = raw
+			.cases
+			.into_iter()
+			.zip(subtask_limits)
+			.enumerate()
+			.map(|(id, (case, subtask_limits))| -> Result<_> {
+				let (time_limit, memory_limit, stack_limit) =
+					parse_limits(id, case.limits.or(subtask_limits).or(raw.limits))?;
+				return Ok(judger::Case {
+					uid: id as u64,
+					score: case.score,
+					input: parse_data(store, id, case.input_hash, &case.input_file)?,
+					answer: parse_data(store, id, case.answer_hash, &case.answer_file)?,
+					time_limit,
+					memory_limit,
+					stack_limit,
+					sample: case.sample,
+					pretest: case.pretest,
+				});
+			})
+			.collect::<Result<Vec<_>>>()?;
+		let output_only = matches!(raw.type_, RawProblemType::OutputOnly);
+		if output_only && (raw.misc.solution.is_some() || !raw.misc.wrong_solutions.is_empty()) {
+			return Err(Error::other("output-only problems take no solutions"));
 		}
 
 		Ok(Self {
-			id:       raw.id,
-			name:     raw.name,
-			checker:  workaround::RemoteCommand::pack(match raw.type_ {
+			id: raw.id,
+			version: 0,
+			fingerprint: String::new(),
+			name: raw.name,
+			checker: workaround::RemoteCommand::pack(match raw.type_ {
 				RawProblemType::Standard => {
 					["python3", "./checkers/standard.py", "%OUTPUT%", "%ANSWER%"]
 						.map(String::from)
@@ -114,70 +590,216 @@ impl Problem {
 						.map(String::from)
 						.to_vec()
 				}
-				RawProblemType::Checker => raw.misc.special_judge.as_ref().unwrap().clone(),
+				RawProblemType::Checker => raw
+					.misc
+					.special_judge
+					.clone()
+					.ok_or(Error::other("spj problems require a special_judge"))?,
+				RawProblemType::OutputOnly => match &raw.misc.special_judge {
+					Some(special_judge) => special_judge.clone(),
+					None => ["python3", "./checkers/standard.py", "%OUTPUT%", "%ANSWER%"]
+						.map(String::from)
+						.to_vec(),
+				},
 			}),
-			data_dir: data_dir.to_str().unwrap().to_string(),
-			cases:    parse_packing(
-				raw.misc.packing,
-				raw.cases
-					.into_iter()
-					.enumerate()
-					.map(|(id, raw)| -> Result<_> {
-						// this part should use crate::Fs feature instead of string concat
-						std::fs::copy(raw.input_file, data_dir.join(format!("in{}", id)))?;
-						std::fs::copy(raw.answer_file, data_dir.join(format!("ans{}", id)))?;
-						return Ok(judger::Case {
-							uid:          id as u64,
-							score:        raw.score,
-							time_limit:   raw.time_limit,
-							memory_limit: match raw.memory_limit {
-								// max(configurable) memory limit: 2G
-								0 => 2 * 1024 * 1024 * 1024,
-								x => x,
-							},
-							dependency:   Vec::new(),
-							pack_score:   0.0,
-						});
-					})
-					.collect::<Result<_>>()?,
-			)?,
-			sandbox:  raw
-				.sandbox
-				.unwrap_or(
-					std::env::var("OJ_SANDBOX").map_or(false, |v| match v.as_str() {
-						"true" => true,
-						"false" => false,
-						_ => panic!("invalid OJ_SANDBOX"),
-					}),
-				),
+			data_dir: store.dir().to_str().unwrap().to_string(),
+			cases,
+			subtasks,
+			sandbox: match raw.sandbox {
+				Some(sandbox) => sandbox,
+				None => match std::env::var("OJ_SANDBOX").as_deref() {
+					Err(_) => false,
+					Ok("true") => true,
+					Ok("false") => false,
+					Ok(_) => return Err(Error::other("invalid OJ_SANDBOX")),
+				},
+			},
+			file_io: parse_file_io(raw.misc.file_io)?,
+			output_only,
+			samples_first: raw.misc.samples_first,
+			show_output: raw.show_output,
+			feedback: raw.feedback,
+			validator: raw.misc.validator.map(workaround::RemoteCommand::pack),
+			solution: raw
+				.misc
+				.solution
+				.map(|solution| parse_solution(store, languages, solution))
+				.transpose()?,
+			wrong_solutions: raw
+				.misc
+				.wrong_solutions
+				.into_iter()
+				.map(|solution| parse_solution(store, languages, solution))
+				.collect::<Result<_>>()?,
+			verification: OnceLock::new(),
+			unverified: OnceLock::new(),
 		})
 	}
+
+	/// a copy with time_limit on every case
+	pub fn with_time_limit(&self, time_limit: u64) -> Self {
+		let problem = Self {
+			cases: self
+				.cases
+				.iter()
+				.map(|case| judger::Case {
+					time_limit,
+					..case.clone()
+				})
+				.collect(),
+			..self.clone()
+		};
+		return Self {
+			fingerprint: problem.fingerprint(),
+			..problem
+		};
+	}
+
+	/// a copy also judging the given (input, answer) hashes, all before anything else
+	/// and with the limits of the first case; failing any of them fails every subtask
+	pub fn with_hacks(&self, hacks: &[(String, String)]) -> Self {
+		// no case to take limits from means no hack was accepted either
+		let Some(first) = self.cases.first().filter(|_| !hacks.is_empty()) else {
+			return self.clone();
+		};
+		let uids = (self.cases.len()..self.cases.len() + hacks.len()).map(|uid| uid as u64);
+		let cases = self
+			.cases
+			.iter()
+			.cloned()
+			.chain(
+				uids.clone()
+					.zip(hacks)
+					.map(|(uid, (input, answer))| judger::Case {
+						uid,
+						score: 0.0,
+						input: input.clone(),
+						answer: answer.clone(),
+						sample: false,
+						pretest: false,
+						..first.clone()
+					}),
+			)
+			.collect();
+		let hack_subtask = self.subtasks.len() as u64;
+		let subtasks = self
+			.subtasks
+			.iter()
+			.map(|subtask| {
+				let mut subtask = subtask.clone();
+				subtask.dependency.push(hack_subtask);
+				subtask
+			})
+			.chain([judger::Subtask {
+				name:       "Hacks".to_string(),
+				policy:     judger::Policy::All,
+				score:      0.0,
+				cases:      uids.collect(),
+				dependency: Vec::new(),
+			}])
+			.collect();
+		let problem = Self {
+			cases,
+			subtasks,
+			..self.clone()
+		};
+		return Self {
+			fingerprint: problem.fingerprint(),
+			..problem
+		};
+	}
 }
 
 pub struct Config {
 	pub server:    Server,
-	pub problems:  HashMap<u64, Arc<Problem>>,
+	/// swapped as a whole on update, jobs keep the snapshot they started with
+	pub problems:  RwLock<HashMap<u64, Arc<Problem>>>,
 	pub languages: HashMap<String, Arc<judger::Language>>,
+	pub data_dir:  PathBuf,
+	pub store:     Store,
+	/// submissions per user
+	pub limiter:   Option<RateLimiter>,
+	/// last version handed out per problem id, kept after deletion
+	versions:      Mutex<HashMap<u64, u64>>,
+	/// every published version per problem id, kept after deletion
+	history:       RwLock<HashMap<u64, Vec<Arc<Problem>>>>,
 }
 
 impl Config {
 	pub fn from(data_dir: &std::path::Path, raw_config: RawConfig) -> Result<Config> {
-		Ok(Config {
+		let config = Config {
+			limiter:   raw_config
+				.server
+				.rate_limit
+				.map(RateLimiter::new)
+				.transpose()?,
 			server:    raw_config.server,
-			problems:  raw_config
-				.problems
-				.into_iter()
-				.map(|raw| -> Result<(u64, Arc<Problem>)> {
-					let problem_dir = data_dir.join(format!("{}", raw.id));
-					fs::create_dir_all(&problem_dir).unwrap();
-					return Ok((raw.id, Arc::new(Problem::from(&problem_dir, raw)?)));
-				})
-				.collect::<Result<HashMap<u64, Arc<Problem>>>>()?,
+			problems:  RwLock::new(HashMap::new()),
 			languages: raw_config
 				.languages
 				.into_iter()
 				.map(|p| (p.name.clone(), Arc::new(p)))
 				.collect(),
-		})
+			data_dir:  data_dir.to_path_buf(),
+			store:     Store::open(&data_dir.join("store"))?,
+			versions:  Mutex::new(HashMap::new()),
+			history:   RwLock::new(HashMap::new()),
+		};
+		for raw in raw_config.problems {
+			let problem = config.load_problem(raw)?;
+			let id = problem.id;
+			config
+				.publish(problem, false)
+				.ok_or(Error::other(format!("duplicate problem id {}", id)))?;
+		}
+		Ok(config)
+	}
+
+	pub fn problem(&self, id: u64) -> Option<Arc<Problem>> {
+		self.problems.read().unwrap().get(&id).cloned()
+	}
+
+	/// build the next version of a problem, not yet visible to new jobs
+	pub fn load_problem(&self, raw: RawProblem) -> Result<Problem> {
+		let version = {
+			let mut versions = self.versions.lock().unwrap();
+			let version = versions.entry(raw.id).or_insert(0);
+			*version += 1;
+			*version
+		};
+		Problem::load(&self.data_dir, &self.store, &self.languages, raw, version)
+	}
+
+	/// make a loaded problem current, it must (replace) or must not (!replace) exist yet
+	pub fn publish(&self, problem: Problem, replace: bool) -> Option<Arc<Problem>> {
+		let problem = Arc::new(problem);
+		let mut problems = self.problems.write().unwrap();
+		if problems.contains_key(&problem.id) != replace {
+			return None;
+		}
+		problems.insert(problem.id, problem.clone());
+		self.history
+			.write()
+			.unwrap()
+			.entry(problem.id)
+			.or_default()
+			.push(problem.clone());
+		return Some(problem);
+	}
+
+	/// a published version, also available after update or deletion
+	pub fn problem_version(&self, id: u64, version: u64) -> Option<Arc<Problem>> {
+		self.problem_history(id)
+			.into_iter()
+			.find(|problem| problem.version == version)
+	}
+
+	pub fn problem_history(&self, id: u64) -> Vec<Arc<Problem>> {
+		self.history
+			.read()
+			.unwrap()
+			.get(&id)
+			.cloned()
+			.unwrap_or_default()
 	}
 }
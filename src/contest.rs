@@ -0,0 +1,709 @@
+/// contests and their phases, contest 0 stands for all problems and users
+use {
+	crate::{common, config, judger, service, team, user},
+	chrono::Utc,
+	cond::cond,
+	lazy_static::lazy_static,
+	serde::{Deserialize, Serialize},
+	std::{
+		collections::HashMap,
+		sync::{Arc, Mutex, MutexGuard},
+		time::Duration,
+	},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+	#[serde(rename = "pending")]
+	Pending,
+	#[serde(rename = "running")]
+	Running,
+	/// ended, contestants may hack accepted submissions of others
+	#[serde(rename = "hacking")]
+	Hacking,
+	/// ended, submissions are rejudged on all cases
+	#[serde(rename = "system_testing")]
+	SystemTesting,
+	#[serde(rename = "finished")]
+	Finished,
+}
+
+/// hacking phase after the contest ends
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawHacking {
+	/// hacks are accepted from the end of the contest until then
+	pub until:        common::Timestamp,
+	/// points per successful hack
+	#[serde(default)]
+	pub score:        f64,
+	/// points taken per unsuccessful hack
+	#[serde(default)]
+	pub penalty:      f64,
+	/// successful hack inputs join the system tests
+	#[serde(default)]
+	pub system_tests: bool,
+}
+
+fn default_icpc_penalty() -> u64 {
+	20
+}
+
+fn default_penalized() -> Vec<judger::Resultat> {
+	vec![
+		judger::Resultat::WrongAnswer,
+		judger::Resultat::RuntimeError,
+		judger::Resultat::TimeLimitExceeded,
+		judger::Resultat::MemoryLimitExceeded,
+		judger::Resultat::PartiallyCorrect,
+	]
+}
+
+/// ranked by problems solved, then penalty time, instead of score
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawIcpc {
+	/// minutes added per rejected try before the accepted one
+	#[serde(default = "default_icpc_penalty")]
+	pub penalty:   u64,
+	/// results of rejected tries, tries with any other result but Accepted do not count
+	#[serde(default = "default_penalized")]
+	pub penalized: Vec<judger::Resultat>,
+	/// minutes before the end from which the public ranklist shows tries as pending
+	#[serde(default)]
+	pub freeze:    u64,
+}
+
+fn default_weight() -> f64 {
+	1.0
+}
+
+/// a problem as it appears in a contest
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RawContestProblem {
+	pub problem_id:       u64,
+	/// A, B, C, ... by position if not given
+	#[serde(default)]
+	pub label:            Option<String>,
+	/// the name of the problem if not given
+	#[serde(default)]
+	pub name:             Option<String>,
+	/// ranklist scores are multiplied by it
+	#[serde(default = "default_weight")]
+	pub weight:           f64,
+	/// per user, the one of the contest if not given
+	#[serde(default)]
+	pub submission_limit: Option<u64>,
+	/// us, replaces the time limit of every case
+	#[serde(default)]
+	pub time_limit:       Option<u64>,
+}
+
+impl RawContestProblem {
+	pub fn new(problem_id: u64) -> Self {
+		Self {
+			problem_id,
+			label: None,
+			name: None,
+			weight: default_weight(),
+			submission_limit: None,
+			time_limit: None,
+		}
+	}
+}
+
+/// label of the problem at index, A to Z then AA, AB, ...
+pub fn label(index: usize) -> String {
+	let mut label = Vec::new();
+	let mut index = index + 1;
+	while index > 0 {
+		index -= 1;
+		label.push(b'A' + (index % 26) as u8);
+		index /= 26;
+	}
+	label.reverse();
+	return String::from_utf8(label).unwrap();
+}
+
+/// self-registration of users and teams, besides user_ids
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawRegistration {
+	/// the end of the contest if not given
+	#[serde(default)]
+	pub until:    Option<common::Timestamp>,
+	/// a valid code registers at once, any other code is refused
+	#[serde(default)]
+	pub codes:    Vec<String>,
+	/// registrations without a code wait for an admin, or are refused if there are codes
+	#[serde(default)]
+	pub approval: bool,
+}
+
+/// contest as posted, without id
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RawContest {
+	pub name:             String,
+	pub from:             common::Timestamp,
+	pub to:               common::Timestamp,
+	/// the ids of problems, if given along with them
+	#[serde(default)]
+	pub problem_ids:      Vec<u64>,
+	/// labeled entries in ranklist order, made from problem_ids if not given
+	#[serde(default)]
+	pub problems:         Vec<RawContestProblem>,
+	pub user_ids:         Vec<u64>,
+	/// per user and problem
+	pub submission_limit: u64,
+	/// judge pretests only while running, everything once ended
+	#[serde(default)]
+	pub pretests:         bool,
+	#[serde(default)]
+	pub hacking:          Option<RawHacking>,
+	#[serde(default)]
+	pub icpc:             Option<RawIcpc>,
+	#[serde(default)]
+	pub registration:     Option<RawRegistration>,
+	/// replaces that of the problems for jobs in the contest
+	#[serde(default)]
+	pub feedback:         Option<config::Feedback>,
+}
+
+/// who is ranked in a contest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Entrant {
+	User(u64),
+	Team(u64),
+}
+
+impl Entrant {
+	/// user ids submitting for it
+	pub fn members(self) -> Vec<u64> {
+		match self {
+			Self::User(user_id) => vec![user_id],
+			Self::Team(team_id) => {
+				team::get(team_id).map_or(Vec::new(), |team| team.user_ids.clone())
+			}
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistrationState {
+	#[serde(rename = "pending")]
+	Pending,
+	#[serde(rename = "approved")]
+	Approved,
+	#[serde(rename = "rejected")]
+	Rejected,
+}
+
+/// a user entering a contest, alone or for a team
+#[derive(Debug, Clone)]
+pub struct Registration {
+	pub user_id:      u64,
+	pub team_id:      Option<u64>,
+	/// shown in the ranklist instead of the user or team name
+	pub display_name: Option<String>,
+	pub state:        RegistrationState,
+	pub created_time: common::Timestamp,
+}
+
+impl Registration {
+	pub fn entrant(&self) -> Entrant {
+		match self.team_id {
+			Some(team_id) => Entrant::Team(team_id),
+			None => Entrant::User(self.user_id),
+		}
+	}
+}
+
+pub type ContestRef = Arc<Contest>;
+pub struct Contest {
+	pub id:        u64,
+	pub raw:       RawContest,
+	/// the system test rejudge, once started
+	system_test:   Mutex<Option<Arc<service::Batch>>>,
+	/// the frozen ranklist has been revealed
+	unfrozen:      Mutex<bool>,
+	registrations: Mutex<Vec<Registration>>,
+}
+
+impl Contest {
+	pub fn phase(&self) -> Phase {
+		let now = Utc::now();
+		cond! {
+			now < self.raw.from => Phase::Pending,
+			now <= self.raw.to => Phase::Running,
+			now <= self.end() => Phase::Hacking,
+			_ => match &*self.system_test.lock().unwrap() {
+				Some(batch) if !batch.finished() => Phase::SystemTesting,
+				// about to start
+				None if self.system_tests() => Phase::SystemTesting,
+				_ => Phase::Finished,
+			},
+		}
+	}
+
+	/// whether the contest time is over, hacking and system tests aside
+	pub fn ended(&self) -> bool {
+		Utc::now() > self.raw.to
+	}
+
+	/// end of the contest, or of the hacking phase if any
+	fn end(&self) -> common::Timestamp {
+		self.raw
+			.hacking
+			.as_ref()
+			.map_or(self.raw.to, |hacking| hacking.until)
+	}
+
+	/// whether submissions are rejudged once ended
+	fn system_tests(&self) -> bool {
+		self.raw.pretests
+			|| self
+				.raw
+				.hacking
+				.as_ref()
+				.is_some_and(|hacking| hacking.system_tests)
+	}
+
+	/// users of user_ids, then approved registrations
+	pub fn entrants(&self) -> Vec<Entrant> {
+		self.raw
+			.user_ids
+			.iter()
+			.map(|&user_id| Entrant::User(user_id))
+			.chain(
+				self.registrations()
+					.iter()
+					.filter(|registration| registration.state == RegistrationState::Approved)
+					.map(Registration::entrant),
+			)
+			.collect()
+	}
+
+	/// the entrant a user submits for, None if not in the contest
+	pub fn entrant_of(&self, user_id: u64) -> Option<Entrant> {
+		self.entrants()
+			.into_iter()
+			.find(|entrant| entrant.members().contains(&user_id))
+	}
+
+	/// name of an entrant in the ranklist
+	pub fn display_name(&self, entrant: Entrant) -> String {
+		let registered = self
+			.registrations()
+			.iter()
+			.find(|registration| {
+				registration.state == RegistrationState::Approved
+					&& registration.entrant() == entrant
+			})
+			.and_then(|registration| registration.display_name.clone());
+		registered.unwrap_or_else(|| match entrant {
+			Entrant::User(user_id) => user::get_list_id()
+				.get(user_id as usize)
+				.map_or(String::new(), |user| user.name.clone()),
+			Entrant::Team(team_id) => {
+				team::get(team_id).map_or(String::new(), |team| team.name.clone())
+			}
+		})
+	}
+
+	pub fn registrations(&self) -> MutexGuard<'_, Vec<Registration>> {
+		self.registrations.lock().unwrap()
+	}
+
+	/// add a registration unless one of its members is in user_ids or registered, and not rejected
+	pub fn register(&self, registration: Registration) -> Result<(), String> {
+		let members = registration.entrant().members();
+		let mut registrations = self.registrations();
+		let mut taken = self.raw.user_ids.iter().copied().chain(
+			registrations
+				.iter()
+				.filter(|registration| registration.state != RegistrationState::Rejected)
+				.flat_map(|registration| registration.entrant().members()),
+		);
+		if let Some(user_id) = taken.find(|user_id| members.contains(user_id)) {
+			return Err(format!(
+				"User {} is in contest {} already.",
+				user_id, self.id
+			));
+		}
+		registrations.push(registration);
+		return Ok(());
+	}
+
+	/// whether registrations are accepted now
+	pub fn registration_open(&self) -> bool {
+		self.raw
+			.registration
+			.as_ref()
+			.is_some_and(|registration| Utc::now() <= registration.until.unwrap_or(self.raw.to))
+	}
+
+	/// the entry of a problem in the contest
+	pub fn problem(&self, problem_id: u64) -> Option<&RawContestProblem> {
+		self.raw
+			.problems
+			.iter()
+			.find(|problem| problem.problem_id == problem_id)
+	}
+
+	/// start of the ranklist freeze, None if everyone or the admin asking sees every result
+	pub fn frozen_since(&self, admin: bool) -> Option<common::Timestamp> {
+		let icpc = self.raw.icpc.as_ref()?;
+		if icpc.freeze == 0 || self.unfrozen() || admin {
+			return None;
+		}
+		return Some(self.raw.to - chrono::Duration::minutes(icpc.freeze as i64));
+	}
+
+	pub fn unfrozen(&self) -> bool {
+		*self.unfrozen.lock().unwrap()
+	}
+
+	/// reveal the frozen ranklist to everyone
+	pub fn unfreeze(&self) {
+		*self.unfrozen.lock().unwrap() = true;
+	}
+
+	/// points of an entrant from hacks by its members, settled ones only
+	pub fn hack_score(&self, entrant: Entrant) -> f64 {
+		let Some(hacking) = &self.raw.hacking else {
+			return 0.0;
+		};
+		let members = entrant.members();
+		get_hacks()
+			.iter()
+			.filter(|hack| hack.contest_id == self.id && members.contains(&hack.hacker_id))
+			.map(|hack| match hack.verdict() {
+				HackVerdict::Successful => hacking.score,
+				HackVerdict::Unsuccessful => -hacking.penalty,
+				_ => 0.0,
+			})
+			.fold(0.0, |total, points| total + points)
+	}
+
+	/// start system tests once ended, after every pretest judging and hack is over
+	fn advance(&self, config: &config::Config) {
+		let mut system_test = self.system_test.lock().unwrap();
+		if !self.system_tests() || system_test.is_some() || Utc::now() <= self.end() {
+			return;
+		}
+		let jobs = service::get_list()
+			.iter()
+			.filter(|job| job.lock().unwrap().raw.contest_id == self.id)
+			.cloned()
+			.collect::<Vec<_>>();
+		let hacks = get_hacks()
+			.iter()
+			.filter(|hack| hack.contest_id == self.id)
+			.cloned()
+			.collect::<Vec<_>>();
+		if jobs
+			.iter()
+			.any(|job| job.lock().unwrap().state == service::SubmissionState::Running)
+			|| hacks
+				.iter()
+				.any(|hack| hack.verdict() == HackVerdict::Pending)
+		{
+			return;
+		}
+
+		// (input, answer) hashes of successful hacks per problem id
+		let mut extra = HashMap::<u64, Vec<(String, String)>>::new();
+		if self
+			.raw
+			.hacking
+			.as_ref()
+			.is_some_and(|hacking| hacking.system_tests)
+		{
+			for hack in hacks
+				.iter()
+				.filter(|hack| hack.verdict() == HackVerdict::Successful)
+			{
+				let job = hack.job.lock().unwrap();
+				let Some(judger::HackOutput::Answer(answer)) = &job.hack_output else {
+					continue;
+				};
+				// a hack failing to be stored is left out
+				if let (Ok(input), Ok(answer)) = (
+					config.store.put(hack.input.as_bytes()),
+					config.store.put(answer.as_bytes()),
+				) {
+					extra
+						.entry(job.problem.id)
+						.or_default()
+						.push((input.hash, answer.hash));
+				}
+			}
+		}
+		// per problem id and version
+		let mut problems = HashMap::<(u64, u64), Arc<config::Problem>>::new();
+		let mut system_problem = |problem: &Arc<config::Problem>| match extra.get(&problem.id) {
+			None => problem.clone(),
+			Some(hacks) => problems
+				.entry((problem.id, problem.version))
+				.or_insert_with(|| Arc::new(problem.with_hacks(hacks)))
+				.clone(),
+		};
+
+		let mut finished = Vec::new();
+		for job in jobs {
+			let hacked = hacked(&job);
+			let mut locked = job.lock().unwrap();
+			let problem = system_problem(&locked.problem);
+			if !locked.pretests && Arc::ptr_eq(&problem, &locked.problem) {
+				continue;
+			}
+			locked.pretests = false;
+			match locked.state {
+				service::SubmissionState::Finished => {
+					locked.pretest_score = Some(if hacked { 0.0 } else { locked.score });
+					locked.pretest_scores = Some(
+						locked
+							.result_subtasks
+							.iter()
+							.map(|subtask| if hacked { 0.0 } else { subtask.score })
+							.collect(),
+					);
+					finished.push((job.clone(), problem));
+				}
+				// no pretest result, just judge them in full
+				service::SubmissionState::Queueing => {
+					locked.problem = problem;
+					locked.clear();
+				}
+				_ => {}
+			}
+		}
+		*system_test = Some(service::rerun_batch(
+			finished,
+			service::Priority::ContestLive,
+		));
+	}
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HackVerdict {
+	#[serde(rename = "pending")]
+	Pending,
+	/// rejected by the validator, no penalty
+	#[serde(rename = "invalid")]
+	Invalid,
+	/// the target failed on the input
+	#[serde(rename = "successful")]
+	Successful,
+	#[serde(rename = "unsuccessful")]
+	Unsuccessful,
+	/// the reference solution or the judger failed, no penalty
+	#[serde(rename = "error")]
+	Error,
+}
+
+/// an input challenging an accepted submission of another contestant
+pub struct Hack {
+	pub id:         u64,
+	pub contest_id: u64,
+	pub hacker_id:  u64,
+	pub target:     service::SubmissionRef,
+	pub input:      Arc<String>,
+	/// the target judged on the input
+	pub job:        service::SubmissionRef,
+}
+
+impl Hack {
+	pub fn verdict(&self) -> HackVerdict {
+		let job = self.job.lock().unwrap();
+		match (&job.state, &job.hack_output) {
+			(service::SubmissionState::Queueing | service::SubmissionState::Running, _) => {
+				HackVerdict::Pending
+			}
+			(_, Some(judger::HackOutput::Invalid(_))) => HackVerdict::Invalid,
+			(service::SubmissionState::Finished, _) => match job.result_final {
+				judger::Resultat::WrongAnswer
+				| judger::Resultat::RuntimeError
+				| judger::Resultat::TimeLimitExceeded
+				| judger::Resultat::MemoryLimitExceeded
+				| judger::Resultat::PartiallyCorrect => HackVerdict::Successful,
+				_ => HackVerdict::Unsuccessful,
+			},
+			_ => HackVerdict::Error,
+		}
+	}
+}
+
+/// a replay of an ended contest by a user, from a start of their own
+pub struct Participation {
+	pub id:         u64,
+	pub contest_id: u64,
+	pub user_id:    u64,
+	pub from:       common::Timestamp,
+	/// as long after from as the contest lasted
+	pub to:         common::Timestamp,
+}
+
+impl Participation {
+	pub fn phase(&self) -> Phase {
+		let now = Utc::now();
+		cond! {
+			now < self.from => Phase::Pending,
+			now <= self.to => Phase::Running,
+			_ => Phase::Finished,
+		}
+	}
+
+	/// contest time of a moment of the participation
+	pub fn contest_time(&self, contest: &Contest, time: common::Timestamp) -> common::Timestamp {
+		contest.raw.from + (time - self.from)
+	}
+
+	/// contest time reached so far
+	pub fn reached(&self, contest: &Contest) -> common::Timestamp {
+		self.contest_time(contest, Utc::now().clamp(self.from, self.to))
+	}
+}
+
+// Lock order: a lock is never taken while holding a later one
+// 1. CONTEST_LIST
+// 2. the mutexes of a contest: system_test, unfrozen, registrations
+// 3. one of the other lists: the job, batch, hack, participation, user and team lists
+// 4. job mutexes, then the judge queue
+// so contests are looked up, and lists cloned, before going on to a later lock.
+lazy_static! {
+	/// contest id - 1 as index
+	static ref CONTEST_LIST: Arc<Mutex<Vec<ContestRef>>> = Arc::new(Mutex::new(Vec::new()));
+	static ref HACK_LIST: Arc<Mutex<Vec<Arc<Hack>>>> = Arc::new(Mutex::new(Vec::new()));
+	static ref PARTICIPATION_LIST: Arc<Mutex<Vec<Arc<Participation>>>> =
+		Arc::new(Mutex::new(Vec::new()));
+}
+
+pub fn get_list() -> MutexGuard<'static, Vec<ContestRef>> {
+	CONTEST_LIST.lock().unwrap()
+}
+
+pub fn get(id: u64) -> Option<ContestRef> {
+	let index = id.checked_sub(1)? as usize;
+	get_list().get(index).cloned()
+}
+
+/// register a contest under the next free id
+pub fn add(raw: RawContest) -> ContestRef {
+	let mut list = get_list();
+	let contest = Arc::new(Contest {
+		id: list.len() as u64 + 1,
+		raw,
+		system_test: Mutex::new(None),
+		unfrozen: Mutex::new(false),
+		registrations: Mutex::new(Vec::new()),
+	});
+	list.push(contest.clone());
+	return contest;
+}
+
+/// replace contest id, system test progress, the reveal and registrations are kept
+pub fn replace(id: u64, raw: RawContest) -> Option<ContestRef> {
+	let mut list = get_list();
+	let old = list.get_mut(id.checked_sub(1)? as usize)?;
+	let system_test = old.system_test.lock().unwrap().clone();
+	let unfrozen = old.unfrozen();
+	let registrations = old.registrations().clone();
+	*old = Arc::new(Contest {
+		id,
+		raw,
+		system_test: Mutex::new(system_test),
+		unfrozen: Mutex::new(unfrozen),
+		registrations: Mutex::new(registrations),
+	});
+	return Some(old.clone());
+}
+
+pub fn get_hacks() -> MutexGuard<'static, Vec<Arc<Hack>>> {
+	HACK_LIST.lock().unwrap()
+}
+
+/// record a hack by hacker and queue request judging it
+pub fn add_hack(
+	contest_id: u64,
+	hacker_id: u64,
+	target: service::SubmissionRef,
+	request: service::Request,
+) -> Arc<Hack> {
+	let mut list = get_hacks();
+	let id = list.len() as u64;
+	let hack = Arc::new(Hack {
+		id,
+		contest_id,
+		hacker_id,
+		target,
+		input: match &request.mode {
+			service::Mode::Hack(input) => input.clone(),
+			_ => Default::default(),
+		},
+		job: service::new_hack(id, request, hacker_id),
+	});
+	list.push(hack.clone());
+	return hack;
+}
+
+pub fn get_participations() -> MutexGuard<'static, Vec<Arc<Participation>>> {
+	PARTICIPATION_LIST.lock().unwrap()
+}
+
+/// the virtual participation of a user in a contest, at most one
+pub fn participation(contest_id: u64, user_id: u64) -> Option<Arc<Participation>> {
+	get_participations()
+		.iter()
+		.find(|participation| {
+			participation.contest_id == contest_id && participation.user_id == user_id
+		})
+		.cloned()
+}
+
+/// start a virtual participation of user in contest at from, None if the user has one
+pub fn add_participation(
+	contest: &Contest,
+	user_id: u64,
+	from: common::Timestamp,
+) -> Option<Arc<Participation>> {
+	let mut list = get_participations();
+	if list.iter().any(|participation| {
+		participation.contest_id == contest.id && participation.user_id == user_id
+	}) {
+		return None;
+	}
+	let participation = Arc::new(Participation {
+		id: list.len() as u64,
+		contest_id: contest.id,
+		user_id,
+		from,
+		to: from + (contest.raw.to - contest.raw.from),
+	});
+	list.push(participation.clone());
+	return Some(participation);
+}
+
+/// problem as judged in contest contest_id, with the time limit of its entry if any
+pub fn judged(contest_id: u64, problem: Arc<config::Problem>) -> Arc<config::Problem> {
+	let time_limit = get(contest_id).and_then(|contest| contest.problem(problem.id)?.time_limit);
+	match time_limit {
+		Some(time_limit) => Arc::new(problem.with_time_limit(time_limit)),
+		None => problem,
+	}
+}
+
+/// whether a hack on job succeeded
+pub fn hacked(job: &service::SubmissionRef) -> bool {
+	get_hacks()
+		.iter()
+		.any(|hack| Arc::ptr_eq(&hack.target, job) && hack.verdict() == HackVerdict::Successful)
+}
+
+/// move contests through their phases in the background
+pub fn start_clock(config: Arc<config::Config>) {
+	std::thread::spawn(move || loop {
+		std::thread::sleep(Duration::from_secs(1));
+		let contests = get_list().clone();
+		for contest in contests {
+			contest.advance(&config);
+		}
+	});
+}
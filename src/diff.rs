@@ -0,0 +1,48 @@
+/// line and token comparison of an output against the answer
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct LineDiff {
+	/// 1-based
+	pub line:   usize,
+	/// None past the last line
+	pub output: Option<String>,
+	pub answer: Option<String>,
+	/// 1-based index of the first differing token, None if only spacing differs
+	pub token:  Option<usize>,
+}
+
+/// split as the standard checker does: trailing spaces and blank lines are ignored
+fn lines(text: &str) -> Vec<&str> {
+	let mut lines = text.split('\n').map(str::trim_end).collect::<Vec<_>>();
+	while lines.last() == Some(&"") {
+		lines.pop();
+	}
+	return lines;
+}
+
+fn tokens(line: Option<&str>) -> Vec<&str> {
+	return line.map_or(Vec::new(), |line| line.split_whitespace().collect());
+}
+
+/// differing lines in order, at most limit of them
+pub fn diff(output: &str, answer: &str, limit: usize) -> Vec<LineDiff> {
+	let output = lines(output);
+	let answer = lines(answer);
+	return (0..output.len().max(answer.len()))
+		.filter(|&index| output.get(index) != answer.get(index))
+		.take(limit)
+		.map(|index| {
+			let (lhs, rhs) = (output.get(index).copied(), answer.get(index).copied());
+			let (lhs_tokens, rhs_tokens) = (tokens(lhs), tokens(rhs));
+			LineDiff {
+				line:   index + 1,
+				output: lhs.map(str::to_string),
+				answer: rhs.map(str::to_string),
+				token:  (0..lhs_tokens.len().max(rhs_tokens.len()))
+					.find(|&token| lhs_tokens.get(token) != rhs_tokens.get(token))
+					.map(|token| token + 1),
+			}
+		})
+		.collect();
+}
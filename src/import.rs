@@ -0,0 +1,431 @@
+/// build config problems from common package layouts
+use {
+	crate::config::{
+		Feedback, RawCase, RawLimits, RawProblem, RawProblemMisc, RawProblemType, RawSubtask,
+	},
+	roxmltree::{Document, Node},
+	serde::{Deserialize, Serialize},
+	std::{
+		collections::HashMap,
+		fs,
+		io::{Error, Result},
+		path::{Path, PathBuf},
+	},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+	/// N.in / N.ans pairs, with an optional problem.yaml
+	#[serde(rename = "directory")]
+	Directory,
+	/// Codeforces Polygon package, described by problem.xml
+	#[serde(rename = "polygon")]
+	Polygon,
+	/// FreeProblemSet xml, cases inlined
+	#[serde(rename = "fps")]
+	Fps,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RawPackage {
+	pub format: Format,
+	pub path:   String,
+}
+
+/// problem.yaml of the directory format, every field optional
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct DirectoryMeta {
+	name:          Option<String>,
+	#[serde(rename = "type")]
+	type_:         Option<RawProblemType>,
+	/// paths relative to the package
+	special_judge: Option<Vec<String>>,
+	subtasks:      Option<Vec<RawSubtask>>,
+	/// total score, split evenly over cases
+	score:         Option<f64>,
+	/// 1-based indices of sample cases
+	samples:       Vec<u64>,
+	#[serde(flatten)]
+	limits:        RawLimits,
+}
+
+/// import a package, extracted files (if any) are written into work_dir
+pub fn import(package: &RawPackage, id: u64, work_dir: &Path) -> Result<RawProblem> {
+	let path = Path::new(&package.path);
+	match package.format {
+		Format::Directory => import_directory(path, id),
+		Format::Polygon => import_polygon(path, id),
+		Format::Fps => import_fps(path, id, work_dir),
+	}
+}
+
+/// import the package of a config entry, fields set in the entry take precedence
+pub fn resolve(raw: RawProblem, work_dir: &Path) -> Result<RawProblem> {
+	let package = match &raw.package {
+		None => return Ok(raw),
+		Some(package) => package,
+	};
+	let mut imported = import(package, raw.id, work_dir)?;
+	if !raw.name.is_empty() {
+		imported.name = raw.name;
+	}
+	// standard is the default, so it cannot override a checker of the package
+	if !matches!(raw.type_, RawProblemType::Standard) {
+		imported.type_ = raw.type_;
+	}
+	imported.misc.special_judge = raw.misc.special_judge.or(imported.misc.special_judge);
+	if matches!(imported.type_, RawProblemType::Checker) && imported.misc.special_judge.is_none() {
+		return Err(Error::other(format!(
+			"package {} has a custom checker, configure a special_judge for it",
+			package.path
+		)));
+	}
+	imported.misc.file_io = raw.misc.file_io.or(imported.misc.file_io);
+	if raw.misc.subtasks.is_some() || raw.misc.packing.is_some() {
+		imported.misc.subtasks = raw.misc.subtasks;
+		imported.misc.packing = raw.misc.packing;
+	}
+	// cases given in the entry amend the imported ones by index
+	if !raw.cases.is_empty() {
+		if raw.cases.len() != imported.cases.len() {
+			return Err(Error::other(format!(
+				"{} cases given for a package of {}",
+				raw.cases.len(),
+				imported.cases.len()
+			)));
+		}
+		for (case, raw_case) in imported.cases.iter_mut().zip(raw.cases) {
+			case.score = raw_case.score;
+			case.sample |= raw_case.sample;
+			case.pretest |= raw_case.pretest;
+			case.limits = raw_case.limits.or(case.limits);
+		}
+	}
+	imported.limits = raw.limits.or(imported.limits);
+	imported.sandbox = raw.sandbox.or(imported.sandbox);
+	imported.show_output = raw.show_output;
+	imported.feedback = raw.feedback;
+	imported.misc.samples_first |= raw.misc.samples_first;
+	imported.misc.unscored_samples |= raw.misc.unscored_samples;
+	imported.misc.validator = raw.misc.validator.or(imported.misc.validator);
+	imported.misc.solution = raw.misc.solution.or(imported.misc.solution);
+	imported.misc.wrong_solutions = raw.misc.wrong_solutions;
+	return Ok(imported);
+}
+
+fn problem(id: u64, name: String, type_: RawProblemType, cases: Vec<RawCase>) -> RawProblem {
+	RawProblem {
+		id,
+		name,
+		type_,
+		misc: RawProblemMisc::default(),
+		cases,
+		sandbox: None,
+		show_output: false,
+		feedback: Feedback::default(),
+		limits: RawLimits::default(),
+		package: None,
+	}
+}
+
+fn case(score: f64, input_file: PathBuf, answer_file: PathBuf) -> RawCase {
+	RawCase {
+		score,
+		input_file: input_file.to_string_lossy().to_string(),
+		answer_file: answer_file.to_string_lossy().to_string(),
+		input_hash: None,
+		answer_hash: None,
+		sample: false,
+		pretest: false,
+		limits: RawLimits::default(),
+	}
+}
+
+fn import_directory(dir: &Path, id: u64) -> Result<RawProblem> {
+	let meta: DirectoryMeta = match fs::read_to_string(dir.join("problem.yaml")) {
+		Err(_) => DirectoryMeta::default(),
+		Ok(content) => serde_yaml::from_str(&content).map_err(Error::other)?,
+	};
+
+	// N.in with N.ans (or N.out), numeric names sort numerically
+	let mut stems = fs::read_dir(dir)?
+		.map(|entry| Ok(entry?.path()))
+		.collect::<Result<Vec<_>>>()?
+		.into_iter()
+		.filter(|path| path.extension().is_some_and(|ext| ext == "in"))
+		.filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+		.collect::<Vec<_>>();
+	stems.sort_by_key(|stem| (stem.parse::<u64>().unwrap_or(u64::MAX), stem.clone()));
+	if stems.is_empty() {
+		return Err(Error::other(format!("no case in {}", dir.display())));
+	}
+
+	let score = meta.score.unwrap_or(100.0) / stems.len() as f64;
+	let mut cases = stems
+		.iter()
+		.map(|stem| {
+			let answer = ["ans", "out"]
+				.iter()
+				.map(|ext| dir.join(format!("{}.{}", stem, ext)))
+				.find(|path| path.is_file())
+				.ok_or(Error::other(format!("case {} has no answer", stem)))?;
+			Ok(case(score, dir.join(format!("{}.in", stem)), answer))
+		})
+		.collect::<Result<Vec<_>>>()?;
+	for &index in &meta.samples {
+		cases
+			.get_mut((index as usize).wrapping_sub(1))
+			.ok_or(Error::other(format!("sample {} out of range", index)))?
+			.sample = true;
+	}
+
+	let name = meta.name.unwrap_or(
+		dir.file_name()
+			.map_or(String::new(), |name| name.to_string_lossy().to_string()),
+	);
+	let mut problem = problem(id, name, meta.type_.unwrap_or_default(), cases);
+	problem.misc.special_judge = meta.special_judge.map(|command| {
+		command
+			.into_iter()
+			.map(|entry| match dir.join(&entry).is_file() {
+				true => dir.join(entry).to_string_lossy().to_string(),
+				false => entry,
+			})
+			.collect()
+	});
+	problem.misc.subtasks = meta.subtasks;
+	problem.limits = meta.limits;
+	return Ok(problem);
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+	node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn children<'a, 'input: 'a>(
+	node: Node<'a, 'input>,
+	tag: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+	node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+fn text_of<T: std::str::FromStr>(node: Option<Node>, what: &str) -> Result<T> {
+	node.and_then(|node| node.text())
+		.and_then(|text| text.trim().parse().ok())
+		.ok_or(Error::other(format!("ill or missing {}", what)))
+}
+
+/// expand printf style patterns as used by polygon, e.g. "tests/%02d"
+fn expand_pattern(pattern: &str, index: u64) -> String {
+	let (prefix, rest) = match pattern.split_once('%') {
+		None => return pattern.to_string(),
+		Some(split) => split,
+	};
+	let spec_len = rest.find('d').map_or(0, |pos| pos + 1);
+	let width: usize = rest[..spec_len.saturating_sub(1)].parse().unwrap_or(0);
+	format!(
+		"{}{:0width$}{}",
+		prefix,
+		index,
+		&rest[spec_len..],
+		width = width
+	)
+}
+
+fn import_polygon(dir: &Path, id: u64) -> Result<RawProblem> {
+	let xml = fs::read_to_string(dir.join("problem.xml"))?;
+	let document = Document::parse(&xml).map_err(Error::other)?;
+	let root = document.root_element();
+
+	let name = child(root, "names")
+		.and_then(|names| child(names, "name"))
+		.and_then(|name| name.attribute("value"))
+		.or(root.attribute("short-name"))
+		.unwrap_or("")
+		.to_string();
+
+	let testset = child(root, "judging")
+		.and_then(|judging| {
+			children(judging, "testset").find(|set| set.attribute("name") == Some("tests"))
+		})
+		.ok_or(Error::other("problem.xml has no tests testset"))?;
+	let time_limit: u64 = text_of(child(testset, "time-limit"), "time-limit")?;
+	let memory_limit: u64 = text_of(child(testset, "memory-limit"), "memory-limit")?;
+	let input_pattern: String = text_of(child(testset, "input-path-pattern"), "input pattern")?;
+	let answer_pattern: String = text_of(child(testset, "answer-path-pattern"), "answer pattern")?;
+
+	// tests are numbered from 1
+	let tests = child(testset, "tests")
+		.map(|tests| children(tests, "test").collect::<Vec<_>>())
+		.unwrap_or_default();
+	if tests.is_empty() {
+		return Err(Error::other("problem.xml has no test"));
+	}
+	let has_points = tests.iter().any(|test| test.attribute("points").is_some());
+	let cases = tests
+		.iter()
+		.enumerate()
+		.map(|(index, test)| {
+			let score = match has_points {
+				true => test
+					.attribute("points")
+					.and_then(|p| p.parse().ok())
+					.unwrap_or(0.0),
+				false => 100.0 / tests.len() as f64,
+			};
+			let index = index as u64 + 1;
+			RawCase {
+				sample: test.attribute("sample") == Some("true"),
+				..case(
+					score,
+					dir.join(expand_pattern(&input_pattern, index)),
+					dir.join(expand_pattern(&answer_pattern, index)),
+				)
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// groups become subtasks, ungrouped tests score on their own
+	let groups = child(testset, "groups")
+		.map(|groups| children(groups, "group").collect::<Vec<_>>())
+		.unwrap_or_default();
+	let subtasks = match groups.is_empty() {
+		true => None,
+		false => {
+			let group_index: HashMap<&str, u64> = groups
+				.iter()
+				.enumerate()
+				.filter_map(|(index, group)| Some((group.attribute("name")?, index as u64 + 1)))
+				.collect();
+			let mut subtasks = groups
+				.iter()
+				.map(|group| {
+					let name = group.attribute("name").unwrap_or("");
+					Ok(RawSubtask {
+						name:       Some(name.to_string()),
+						policy:     match group.attribute("points-policy") {
+							Some("each-test") => crate::judger::Policy::Sum,
+							_ => crate::judger::Policy::All,
+						},
+						cases:      tests
+							.iter()
+							.enumerate()
+							.filter(|(_, test)| test.attribute("group") == Some(name))
+							.map(|(index, _)| index as u64 + 1)
+							.collect(),
+						score:      group.attribute("points").and_then(|p| p.parse().ok()),
+						dependency: child(*group, "dependencies")
+							.map(|deps| {
+								children(deps, "dependency")
+									.map(|dep| {
+										dep.attribute("group")
+											.and_then(|name| group_index.get(name).copied())
+											.ok_or(Error::other(format!(
+												"group {} has ill dependency",
+												name
+											)))
+									})
+									.collect::<Result<Vec<_>>>()
+							})
+							.transpose()?
+							.unwrap_or_default(),
+						limits:     RawLimits::default(),
+					})
+				})
+				.collect::<Result<Vec<_>>>()?;
+			for (index, _) in tests
+				.iter()
+				.enumerate()
+				.filter(|(_, test)| test.attribute("group").is_none())
+			{
+				subtasks.push(RawSubtask {
+					name:       None,
+					policy:     crate::judger::Policy::Sum,
+					cases:      vec![index as u64 + 1],
+					score:      None,
+					dependency: Vec::new(),
+					limits:     RawLimits::default(),
+				});
+			}
+			Some(subtasks)
+		}
+	};
+
+	// testlib standard checkers map onto ours, others need a configured special_judge,
+	// yesno too as it ignores case
+	let checker = child(root, "assets")
+		.and_then(|assets| child(assets, "checker"))
+		.and_then(|checker| checker.attribute("name"))
+		.unwrap_or("std::wcmp.cpp");
+	let type_ = match checker {
+		"std::wcmp.cpp" | "std::lcmp.cpp" | "std::ncmp.cpp" | "std::hcmp.cpp" => {
+			RawProblemType::Standard
+		}
+		_ => RawProblemType::Checker,
+	};
+
+	let mut problem = problem(id, name, type_, cases);
+	problem.misc.subtasks = subtasks;
+	problem.limits = RawLimits {
+		time_limit:   Some(time_limit * 1000),
+		memory_limit: Some(memory_limit),
+		stack_limit:  None,
+	};
+	return Ok(problem);
+}
+
+fn import_fps(file: &Path, id: u64, work_dir: &Path) -> Result<RawProblem> {
+	let xml = fs::read_to_string(file)?;
+	let document = Document::parse(&xml).map_err(Error::other)?;
+	let item = child(document.root_element(), "item").ok_or(Error::other("fps has no item"))?;
+
+	let name = child(item, "title")
+		.and_then(|title| title.text())
+		.unwrap_or("")
+		.to_string();
+	let time_node = child(item, "time_limit");
+	let time_limit: f64 = text_of(time_node, "time_limit")?;
+	let memory_node = child(item, "memory_limit");
+	let memory_limit: f64 = text_of(memory_node, "memory_limit")?;
+
+	let inputs = children(item, "test_input").collect::<Vec<_>>();
+	let answers = children(item, "test_output").collect::<Vec<_>>();
+	if inputs.is_empty() || inputs.len() != answers.len() {
+		return Err(Error::other(
+			"fps test_input and test_output do not pair up",
+		));
+	}
+	fs::create_dir_all(work_dir)?;
+	let cases = inputs
+		.iter()
+		.zip(answers.iter())
+		.enumerate()
+		.map(|(index, (input, answer))| {
+			let input_file = work_dir.join(format!("{}.in", index + 1));
+			let answer_file = work_dir.join(format!("{}.ans", index + 1));
+			fs::write(&input_file, input.text().unwrap_or(""))?;
+			fs::write(&answer_file, answer.text().unwrap_or(""))?;
+			Ok(case(100.0 / inputs.len() as f64, input_file, answer_file))
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	// its special judge needs a configured special_judge
+	let type_ = match child(item, "spj") {
+		None => RawProblemType::Standard,
+		Some(_) => RawProblemType::Checker,
+	};
+	let mut problem = problem(id, name, type_, cases);
+	problem.limits = RawLimits {
+		time_limit:   Some(match time_node.and_then(|node| node.attribute("unit")) {
+			Some("ms") => time_limit * 1e3,
+			_ => time_limit * 1e6,
+		} as u64),
+		memory_limit: Some(match memory_node.and_then(|node| node.attribute("unit")) {
+			Some("kb") => memory_limit * 1024.0,
+			_ => memory_limit * 1048576.0,
+		} as u64),
+		stack_limit:  None,
+	};
+	return Ok(problem);
+}
@@ -3,6 +3,13 @@ pub use common::{judger, workaround};
 pub mod api;
 pub mod callcc;
 pub mod config;
+pub mod contest;
+pub mod diff;
+pub mod import;
+pub mod limit;
 pub mod response;
 pub mod service;
+pub mod store;
+pub mod team;
 pub mod user;
+pub mod verify;
@@ -0,0 +1,61 @@
+/// per-user token buckets for submissions
+use {
+	serde::{Deserialize, Serialize},
+	std::{
+		collections::HashMap,
+		io::{Error, Result},
+		sync::Mutex,
+		time::{Duration, Instant},
+	},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RawRateLimit {
+	/// submissions allowed at once
+	pub burst: u64,
+	/// submissions regained per second
+	pub rate:  f64,
+}
+
+struct Bucket {
+	tokens:  f64,
+	updated: Instant,
+}
+
+pub struct RateLimiter {
+	limit:   RawRateLimit,
+	buckets: Mutex<HashMap<u64, Bucket>>,
+}
+
+impl RateLimiter {
+	pub fn new(limit: RawRateLimit) -> Result<Self> {
+		if limit.burst == 0 || limit.rate <= 0.0 || limit.rate.is_nan() {
+			return Err(Error::other("rate_limit needs positive burst and rate"));
+		}
+		return Ok(Self {
+			limit,
+			buckets: Mutex::new(HashMap::new()),
+		});
+	}
+
+	/// take one token of user, or tell how long until one is available
+	pub fn acquire(&self, user: u64) -> std::result::Result<(), Duration> {
+		let now = Instant::now();
+		let burst = self.limit.burst as f64;
+		let mut buckets = self.buckets.lock().unwrap();
+		let bucket = buckets.entry(user).or_insert(Bucket {
+			tokens:  burst,
+			updated: now,
+		});
+		let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+		bucket.tokens = (bucket.tokens + elapsed * self.limit.rate).min(burst);
+		bucket.updated = now;
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(());
+		}
+		return Err(Duration::from_secs_f64(
+			(1.0 - bucket.tokens) / self.limit.rate,
+		));
+	}
+}
@@ -1,17 +1,36 @@
 use {
 	actix_web::{middleware::Logger, post, web, App, HttpServer, Responder},
-	clap::Parser,
+	clap::{Parser, Subcommand},
 	env_logger, log,
-	oj::config::Config,
+	oj::{config::Config, import},
 	serde_json::from_str,
 };
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
-	#[arg(short, long)]
-	config:     String,
+	#[arg(short, long, required = true)]
+	config:     Option<String>,
 	#[arg(short, long, default_value_t = false)]
 	flush_data: bool,
+	#[command(subcommand)]
+	command:    Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Convert a problem package into a config problem entry, printed as json
+	Import {
+		#[arg(long, value_enum)]
+		format:   import::Format,
+		#[arg(long)]
+		path:     String,
+		#[arg(long, default_value_t = 0)]
+		id:       u64,
+		/// where inlined cases are extracted to
+		#[arg(long, default_value = "./data/import")]
+		data_dir: String,
+	},
 }
 
 // DO NOT REMOVE: used in automatic testing
@@ -27,17 +46,37 @@ async fn exit() -> impl Responder {
 async fn main() -> std::io::Result<()> {
 	env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 	let args = Args::parse();
+	if let Some(Command::Import {
+		format,
+		path,
+		id,
+		data_dir,
+	}) = args.command
+	{
+		let package = import::RawPackage {
+			format,
+			path,
+		};
+		let problem = import::import(&package, id, std::path::Path::new(&data_dir))?;
+		println!("{}", serde_json::to_string_pretty(&problem)?);
+		return Ok(());
+	}
 	let data_dir = std::path::Path::new("./data");
 	let config = web::Data::new(Config::from(
 		&data_dir,
-		from_str(&std::fs::read_to_string(args.config)?)?,
+		from_str(&std::fs::read_to_string(args.config.unwrap())?)?,
 	)?);
 
+	oj::contest::start_clock(config.clone().into_inner());
+	oj::verify::start(config.clone().into_inner());
+
 	HttpServer::new({
 		let config = config.clone();
 		move || {
 			App::new()
 				.app_data(config.clone())
+				// test data uploads
+				.app_data(web::PayloadConfig::new(1 << 30))
 				.wrap(Logger::default())
 				// DO NOT REMOVE: used in automatic testing
 				.service(exit)
@@ -46,8 +85,40 @@ async fn main() -> std::io::Result<()> {
 				.service(oj::api::jobs::delete_id)
 				.service(oj::api::jobs::get)
 				.service(oj::api::jobs::get_id)
+				.service(oj::api::jobs::get_case)
 				.service(oj::api::users::post)
 				.service(oj::api::users::get)
+				.service(oj::api::teams::post)
+				.service(oj::api::teams::get)
+				.service(oj::api::teams::get_id)
+				.service(oj::api::problems::post)
+				.service(oj::api::problems::put_id)
+				.service(oj::api::problems::delete_id)
+				.service(oj::api::problems::get)
+				.service(oj::api::problems::get_id)
+				.service(oj::api::problems::get_versions)
+				.service(oj::api::problems::get_samples)
+				.service(oj::api::data::post)
+				.service(oj::api::data::get_hash)
+				.service(oj::api::rejudges::post)
+				.service(oj::api::rejudges::get_id)
+				.service(oj::api::runs::post)
+				.service(oj::api::runs::get_id)
+				.service(oj::api::stress_tests::post)
+				.service(oj::api::stress_tests::get_id)
+				.service(oj::api::contests::post)
+				.service(oj::api::contests::get)
+				.service(oj::api::contests::get_id)
+				.service(oj::api::contests::get_ranklist)
+				.service(oj::api::contests::post_unfreeze)
+				.service(oj::api::contests::post_virtual)
+				.service(oj::api::contests::get_virtual)
+				.service(oj::api::contests::post_registration)
+				.service(oj::api::contests::get_registrations)
+				.service(oj::api::contests::post_review)
+				.service(oj::api::hacks::post)
+				.service(oj::api::hacks::get)
+				.service(oj::api::hacks::get_id)
 		}
 	})
 	.bind((config.server.bind_address.as_str(), config.server.bind_port))?
@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+/// code and reason: 1 ERR_INVALID_ARGUMENT, 2 ERR_INVALID_STATE, 3 ERR_NOT_FOUND,
+/// 4 ERR_RATE_LIMIT, 5 ERR_EXTERNAL, 6 ERR_INTERNAL
 #[derive(Serialize, Debug)]
 pub struct Error {
 	pub code:    u64,
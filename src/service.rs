@@ -8,20 +8,34 @@ use {
 	serde::{Deserialize, Serialize},
 	serde_json::{from_str, json},
 	std::{
-		io::{BufRead, BufReader, Write},
-		process::{Command, Stdio},
-		sync::{
-			mpsc::{Receiver, Sender},
-			Arc, Mutex, MutexGuard,
-		},
+		collections::{HashMap, VecDeque},
+		io::{BufRead, BufReader, Lines, Write},
+		process::{Child, ChildStdout, Command, Stdio},
+		sync::{Arc, Condvar, Mutex, MutexGuard},
 	},
 };
 
 pub struct Request {
 	pub source:     Arc<String>,
-	pub language:   Arc<judger::Language>,
+	/// None for output-only problems
+	pub language:   Option<Arc<judger::Language>>,
 	pub problem:    Arc<config::Problem>,
 	pub submission: Arc<crate::api::jobs::Submission>,
+	pub mode:       Mode,
+	/// during a contest with pretests
+	pub pretests:   bool,
+}
+
+/// what a job runs, as judger::Mode; the rest of it is filled in from the problem
+pub enum Mode {
+	/// on every case
+	Judge,
+	/// input of a custom run
+	Custom(Arc<String>),
+	/// input of a hack, judged alone against the answer of the reference solution
+	Hack(Arc<String>),
+	/// generator and brute force of a stress test
+	Stress(Arc<judger::Stress>),
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -36,19 +50,35 @@ pub enum SubmissionState {
 pub type SubmissionRef = Arc<Mutex<Submission>>;
 pub struct Submission {
 	// info
-	pub id:             u64,
-	pub source:         Arc<String>,
-	pub language:       Arc<judger::Language>,
-	pub problem:        Arc<config::Problem>,
-	pub raw:            Arc<crate::api::jobs::Submission>,
+	pub id:              u64,
+	pub source:          Arc<String>,
+	pub language:        Option<Arc<judger::Language>>,
+	pub problem:         Arc<config::Problem>,
+	pub raw:             Arc<crate::api::jobs::Submission>,
+	pub mode:            Mode,
+	/// judge pretests only, cleared for system tests
+	pub pretests:        bool,
 	// result
-	pub created_time:   crate::common::Timestamp,
-	pub updated_time:   crate::common::Timestamp,
-	pub state:          SubmissionState,
-	pub result_final:   judger::Resultat,
-	pub result_compile: judger::CaseResult,
-	pub result_cases:   Vec<judger::CaseResult>,
-	pub score:          f64,
+	pub created_time:    crate::common::Timestamp,
+	pub updated_time:    crate::common::Timestamp,
+	pub state:           SubmissionState,
+	pub result_final:    judger::Resultat,
+	pub result_compile:  judger::CaseResult,
+	pub result_cases:    Vec<judger::CaseResult>,
+	pub result_subtasks: Vec<judger::SubtaskResult>,
+	/// kept for failed cases only
+	pub result_outputs:  Vec<Option<judger::CaseOutput>>,
+	pub score:           f64,
+	/// custom runs only
+	pub run_output:      Option<judger::RunOutput>,
+	/// score on pretests, kept once system tests start
+	pub pretest_score:   Option<f64>,
+	/// subtask scores on pretests, likewise
+	pub pretest_scores:  Option<Vec<f64>>,
+	/// hacks only
+	pub hack_output:     Option<judger::HackOutput>,
+	/// stress tests only
+	pub stress_output:   Option<judger::StressOutput>,
 }
 
 impl Submission {
@@ -62,33 +92,133 @@ impl Submission {
 			result_final: judger::Resultat::Waiting,
 			result_compile: judger::CaseResult::Waiting,
 			score: 0.0,
+			run_output: None,
+			pretest_score: None,
+			pretest_scores: None,
+			hack_output: None,
+			stress_output: None,
+			result_outputs: request.problem.cases.iter().map(|_| None).collect(),
 			result_cases: request
 				.problem
 				.cases
 				.iter()
 				.map(|_| judger::CaseResult::Waiting)
 				.collect(),
+			result_subtasks: request
+				.problem
+				.subtasks
+				.iter()
+				.map(|_| judger::SubtaskResult::waiting())
+				.collect(),
 			// info
 			source: request.source,
 			language: request.language,
 			problem: request.problem,
 			raw: request.submission,
+			mode: request.mode,
+			pretests: request.pretests,
 		}
 	}
 
-	// before rerun job
+	// before rerun job, problem may have been switched to another version
 	pub fn clear(&mut self) {
 		self.state = SubmissionState::Queueing;
 		self.result_final = judger::Resultat::Waiting;
 		self.result_compile = judger::CaseResult::Waiting;
-		for case in self.result_cases.iter_mut() {
-			*case = judger::CaseResult::Waiting
+		self.result_cases = self
+			.problem
+			.cases
+			.iter()
+			.map(|_| judger::CaseResult::Waiting)
+			.collect();
+		self.result_outputs = self.problem.cases.iter().map(|_| None).collect();
+		self.result_subtasks = self
+			.problem
+			.subtasks
+			.iter()
+			.map(|_| judger::SubtaskResult::waiting())
+			.collect();
+	}
+}
+
+type Updates = Lines<BufReader<ChildStdout>>;
+
+/// start a judger container on the data dir, fed with request
+fn start_judger(cpuid: u8, data_dir: &str, request: serde_json::Value) -> Result<(Child, Updates)> {
+	let mut child = Command::new("docker")
+		.args([
+			"run",
+			// once container
+			"--rm",
+			// bind stdin
+			"-i",
+			// bind cpu
+			format!("--cpuset-cpus={}", cpuid).as_str(),
+			// memory limit
+			"-m=2G",
+			// no network access
+			"--network=none",
+			// map data dir ro
+			format!("-v=./{}:/work/a/data:ro", data_dir).as_str(),
+			// start container
+			"oj-judger",
+		])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::null())
+		.spawn()?;
+
+	// transfer request to container via stdin
+	child
+		.stdin
+		.take()
+		.ok_or(anyhow!("child has no stdin"))?
+		.write_all(request.to_string().as_bytes())?;
+
+	let recv = BufReader::new(child.stdout.take().ok_or(anyhow!("child has no stdout"))?).lines();
+	return Ok((child, recv));
+}
+
+/// check case inputs with the validator of problem in a judger, (uid, complaint) of invalid ones
+pub fn validate(problem: &config::Problem, cases: &[judger::Case]) -> Result<Vec<(u64, String)>> {
+	let Some(validator) = &problem.validator else {
+		return Ok(Vec::new());
+	};
+	let (mut child, recv) = start_judger(
+		0,
+		&problem.data_dir,
+		json!({
+			"solution": { "Outputs": [] },
+			"sandbox": problem.sandbox,
+			"cases": cases,
+			"subtasks": [],
+			"checker": &problem.checker,
+			"file_io": &problem.file_io,
+			"mode": { "Validate": validator },
+		}),
+	)?;
+	let mut complaints = Vec::new();
+	let mut finished = false;
+	for update_str in recv {
+		match from_str(&update_str?)? {
+			judger::Update::Validation(uid, Some(complaint)) => complaints.push((uid, complaint)),
+			judger::Update::Finish(_, _) => finished = true,
+			judger::Update::Error(err) => return Err(anyhow!(err)),
+			_ => {}
 		}
 	}
+	let status = child.wait()?;
+
+	return cond! {
+		!status.success() => Err(anyhow!("judger failed")),
+		!finished => Err(anyhow!("judger disconnected")),
+		_ => Ok(complaints),
+	};
 }
 
-fn runner(cpuid: u8, recv: Receiver<SubmissionRef>) {
-	while let Ok(submission) = recv.recv() {
+fn runner(cpuid: u8, queue: Arc<JobQueue>) {
+	loop {
+		let submission = queue.pop();
 		log::debug!("grab test");
 
 		// try_catch wrapper
@@ -109,52 +239,49 @@ fn runner(cpuid: u8, recv: Receiver<SubmissionRef>) {
 				// lock submission, start runner
 				let (mut child, mut recv) = {
 					let submission = submission.lock().unwrap();
-					let mut child = Command::new("docker")
-						.args([
-							"run",
-							// once container
-							"--rm",
-							// bind stdin
-							"-i",
-							// bind cpu
-							format!("--cpuset-cpus={}", cpuid).as_str(),
-							// memory limit
-							"-m=2G",
-							// no network access
-							"--network=none",
-							// map data dir ro
-							format!("-v=./{}:/work/a/data:ro", &submission.problem.data_dir).as_str(),
-							// start container
-							"oj-judger",
-						])
-						.stdin(Stdio::piped())
-						.stdout(Stdio::piped())
-						.stderr(Stdio::null())
-						.spawn()?;
-
-					// transfer request to container via stdin
-					child
-						.stdin
-						.take()
-						.ok_or(anyhow!("child has no stdin"))?
-						.write_all(
-							json!({
-								"code": {
-									"language": submission.language.as_ref(),
-									"source": &submission.source,
-								},
-								"sandbox": submission.problem.sandbox,
-								"cases": &submission.problem.cases,
-								"checker": &submission.problem.checker,
-							})
-							.to_string()
-							.as_bytes(),
-						)?;
-
-					let recv =
-						BufReader::new(child.stdout.take().ok_or(anyhow!("child has no stdout"))?)
-							.lines();
-					(child, recv)
+					// custom runs, hacks and stress tests take limits from the first case
+					let cases = match submission.mode {
+						Mode::Judge => &submission.problem.cases,
+						_ => submission
+							.problem
+							.cases
+							.get(..1)
+							.ok_or(anyhow!("problem has no case to take limits from"))?,
+					};
+					start_judger(
+						cpuid,
+						&submission.problem.data_dir,
+						json!({
+							"solution": match &submission.language {
+								Some(language) => json!({
+									"Code": {
+										"language": language.as_ref(),
+										"source": &submission.source,
+									},
+								}),
+								None => json!({
+									"Outputs": submission.raw.outputs.as_deref().unwrap_or(&Vec::new()),
+								}),
+							},
+							"sandbox": submission.problem.sandbox,
+							"cases": cases,
+							"subtasks": &submission.problem.subtasks,
+							"checker": &submission.problem.checker,
+							"file_io": &submission.problem.file_io,
+							"samples_first": submission.problem.samples_first,
+							"pretests_only": submission.pretests,
+							"mode": match &submission.mode {
+								Mode::Judge => json!("Judge"),
+								Mode::Custom(input) => json!({ "Custom": input }),
+								Mode::Hack(input) => json!({ "Hack": {
+									"input": input,
+									"reference": &submission.problem.solution,
+									"validator": &submission.problem.validator,
+								}}),
+								Mode::Stress(stress) => json!({ "Stress": stress }),
+							},
+						}),
+					)?
 				};
 
 				while let Some(Ok(update_str)) = recv.next() {
@@ -168,6 +295,23 @@ fn runner(cpuid: u8, recv: Receiver<SubmissionRef>) {
 						judger::Update::Case(id, data) => {
 							submission.result_cases[id as usize] = data;
 						}
+						judger::Update::Output(id, data) => {
+							submission.result_outputs[id as usize] = Some(data);
+						}
+						judger::Update::Run(data) => {
+							submission.run_output = Some(data);
+						}
+						judger::Update::Hack(data) => {
+							submission.hack_output = Some(data);
+						}
+						judger::Update::Stress(data) => {
+							submission.stress_output = Some(data);
+						}
+						// only asked for by validate
+						judger::Update::Validation(_, _) => {}
+						judger::Update::Subtask(id, data) => {
+							submission.result_subtasks[id as usize] = data;
+						}
 						judger::Update::Finish(cur, score) => {
 							submission.result_final = cur;
 							submission.score = score;
@@ -195,31 +339,201 @@ fn runner(cpuid: u8, recv: Receiver<SubmissionRef>) {
 	}
 }
 
+/// judge order, earlier classes go first
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+	/// submissions to a contest
+	#[serde(rename = "contest_live")]
+	ContestLive,
+	#[serde(rename = "normal")]
+	Normal,
+	#[serde(rename = "custom_run")]
+	CustomRun,
+	/// bulk work, only run when nothing else is waiting
+	#[serde(rename = "rejudge")]
+	Rejudge,
+}
+const PRIORITY_COUNT: usize = 4;
+
+/// jobs of one priority, users with pending jobs take turns
+/// generic over the job only to be tested without judging anything
+struct FairQueue<T = Mutex<Submission>> {
+	/// front user is served next, then moves to the back
+	turns: VecDeque<u64>,
+	jobs:  HashMap<u64, VecDeque<Arc<T>>>,
+}
+
+impl<T> Default for FairQueue<T> {
+	fn default() -> Self {
+		Self {
+			turns: VecDeque::new(),
+			jobs:  HashMap::new(),
+		}
+	}
+}
+
+impl<T> FairQueue<T> {
+	fn push(&mut self, user: u64, job: Arc<T>) {
+		let jobs = self.jobs.entry(user).or_default();
+		if jobs.is_empty() {
+			self.turns.push_back(user);
+		}
+		jobs.push_back(job);
+	}
+	fn pop(&mut self) -> Option<Arc<T>> {
+		let user = self.turns.pop_front()?;
+		let jobs = self.jobs.get_mut(&user).unwrap();
+		let job = jobs.pop_front().unwrap();
+		if jobs.is_empty() {
+			self.jobs.remove(&user);
+		} else {
+			self.turns.push_back(user);
+		}
+		return Some(job);
+	}
+	fn len(&self) -> usize {
+		self.jobs.values().map(VecDeque::len).sum()
+	}
+	/// (turn of the user, index in the queue of the user)
+	fn find(&self, job: &Arc<T>) -> Option<(usize, usize)> {
+		self.turns.iter().enumerate().find_map(|(turn, user)| {
+			let index = self.jobs[user]
+				.iter()
+				.position(|other| Arc::ptr_eq(other, job))?;
+			Some((turn, index))
+		})
+	}
+	/// number of jobs served before the given one
+	fn position(&self, job: &Arc<T>) -> Option<usize> {
+		let (turn, index) = self.find(job)?;
+		// each round serves one job per user, users before this one also in its round
+		let position = self
+			.turns
+			.iter()
+			.enumerate()
+			.map(|(other, user)| self.jobs[user].len().min(index + (other < turn) as usize))
+			.sum();
+		return Some(position);
+	}
+	fn remove(&mut self, job: &Arc<T>) -> bool {
+		let Some((turn, index)) = self.find(job) else {
+			return false;
+		};
+		let user = self.turns[turn];
+		let jobs = self.jobs.get_mut(&user).unwrap();
+		jobs.remove(index);
+		if jobs.is_empty() {
+			self.jobs.remove(&user);
+			self.turns.remove(turn);
+		}
+		return true;
+	}
+}
+
+struct JobQueue<T = Mutex<Submission>> {
+	queues: Mutex<[FairQueue<T>; PRIORITY_COUNT]>,
+	ready:  Condvar,
+}
+
+impl<T> JobQueue<T> {
+	fn push(&self, job: Arc<T>, user: u64, priority: Priority) {
+		self.queues.lock().unwrap()[priority as usize].push(user, job);
+		self.ready.notify_one();
+	}
+	fn pop(&self) -> Arc<T> {
+		let mut queues = self.queues.lock().unwrap();
+		loop {
+			if let Some(job) = queues.iter_mut().find_map(FairQueue::pop) {
+				return job;
+			}
+			queues = self.ready.wait(queues).unwrap();
+		}
+	}
+	fn position(&self, job: &Arc<T>) -> Option<usize> {
+		let queues = self.queues.lock().unwrap();
+		let mut ahead = 0;
+		for queue in queues.iter() {
+			if let Some(position) = queue.position(job) {
+				return Some(ahead + position);
+			}
+			ahead += queue.len();
+		}
+		return None;
+	}
+	/// jobs waiting in classes before the given one
+	fn len_before(&self, priority: Priority) -> usize {
+		self.queues.lock().unwrap()[..priority as usize]
+			.iter()
+			.map(FairQueue::len)
+			.sum()
+	}
+	fn remove(&self, job: &Arc<T>) -> bool {
+		self.queues
+			.lock()
+			.unwrap()
+			.iter_mut()
+			.any(|queue| queue.remove(job))
+	}
+}
+
 struct JobRunner {
-	send: Sender<SubmissionRef>,
+	queue: Arc<JobQueue>,
 }
 
 impl JobRunner {
 	fn new() -> Self {
-		let (send, recv) = std::sync::mpsc::channel::<SubmissionRef>();
-		std::thread::spawn(move || {
-			runner(0, recv);
+		let queue = Arc::new(JobQueue {
+			queues: Mutex::new(Default::default()),
+			ready:  Condvar::new(),
+		});
+		std::thread::spawn({
+			let queue = queue.clone();
+			move || {
+				runner(0, queue);
+			}
 		});
 		Self {
-			send,
+			queue,
 		}
 	}
-	fn send(&self, job: SubmissionRef) {
-		self.send.send(job).unwrap();
+	fn send(&self, job: SubmissionRef, user: u64, priority: Priority) {
+		self.queue.push(job, user, priority);
+	}
+}
+
+/// jobs rejudged together, with their results before
+pub struct Batch {
+	pub id:           u64,
+	pub created_time: crate::common::Timestamp,
+	pub jobs:         Vec<(SubmissionRef, judger::Resultat)>,
+}
+
+impl Batch {
+	pub fn finished(&self) -> bool {
+		self.jobs.iter().all(|(job, _)| {
+			!matches!(
+				job.lock().unwrap().state,
+				SubmissionState::Queueing | SubmissionState::Running
+			)
+		})
 	}
 }
 
 lazy_static! {
 	static ref SUBMISSION_LIST: Arc<Mutex<Vec<SubmissionRef>>> = Arc::new(Mutex::new(Vec::new()));
 	static ref JOB_RUNNER: JobRunner = JobRunner::new();
+	static ref BATCH_LIST: Arc<Mutex<Vec<Arc<Batch>>>> = Arc::new(Mutex::new(Vec::new()));
+	/// custom runs, kept apart from scored submissions
+	static ref RUN_LIST: Arc<Mutex<Vec<SubmissionRef>>> = Arc::new(Mutex::new(Vec::new()));
+	static ref STRESS_LIST: Arc<Mutex<Vec<SubmissionRef>>> = Arc::new(Mutex::new(Vec::new()));
 }
 
 pub fn new_job(request: Request) -> SubmissionRef {
+	let user = request.submission.user_id;
+	let priority = match request.submission.contest_id {
+		0 => Priority::Normal,
+		_ => Priority::ContestLive,
+	};
 	let submission = {
 		let mut job_list = SUBMISSION_LIST.lock().unwrap();
 		let job = Arc::new(Mutex::new(Submission::new(job_list.len() as u64, request)));
@@ -227,27 +541,203 @@ pub fn new_job(request: Request) -> SubmissionRef {
 		job
 	};
 
-	JOB_RUNNER.send(submission.clone());
+	JOB_RUNNER.send(submission.clone(), user, priority);
 	return submission;
 }
 
-pub fn rerun_job(submission: SubmissionRef) -> SubmissionRef {
-	submission.lock().unwrap().clear();
-	JOB_RUNNER.send(submission.clone());
+/// queue a custom run, which is not a submission and never rejudged
+pub fn new_run(request: Request) -> SubmissionRef {
+	let user = request.submission.user_id;
+	let run = {
+		let mut run_list = RUN_LIST.lock().unwrap();
+		let run = Arc::new(Mutex::new(Submission::new(run_list.len() as u64, request)));
+		run_list.push(run.clone());
+		run
+	};
+
+	JOB_RUNNER.send(run.clone(), user, Priority::CustomRun);
+	return run;
+}
+
+/// queue a stress test, which like a custom run is not a submission
+pub fn new_stress(request: Request) -> SubmissionRef {
+	let user = request.submission.user_id;
+	let stress = {
+		let mut stress_list = STRESS_LIST.lock().unwrap();
+		let stress = Arc::new(Mutex::new(Submission::new(
+			stress_list.len() as u64,
+			request,
+		)));
+		stress_list.push(stress.clone());
+		stress
+	};
+
+	JOB_RUNNER.send(stress.clone(), user, Priority::CustomRun);
+	return stress;
+}
+
+/// queue the judging of a hack, listed with the hack rather than among jobs
+pub fn new_hack(id: u64, request: Request, hacker: u64) -> SubmissionRef {
+	let hack = Arc::new(Mutex::new(Submission::new(id, request)));
+	JOB_RUNNER.send(hack.clone(), hacker, Priority::ContestLive);
+	return hack;
+}
+
+/// queue the judging of a solution shipped with a problem, listed nowhere
+pub fn new_check(request: Request) -> SubmissionRef {
+	let user = request.submission.user_id;
+	let check = Arc::new(Mutex::new(Submission::new(0, request)));
+	JOB_RUNNER.send(check.clone(), user, Priority::Normal);
+	return check;
+}
+
+/// rejudge against the given problem version
+pub fn rerun_job(
+	submission: SubmissionRef,
+	problem: Arc<config::Problem>,
+	priority: Priority,
+) -> SubmissionRef {
+	let user = {
+		let mut submission = submission.lock().unwrap();
+		submission.problem = problem;
+		submission.clear();
+		submission.raw.user_id
+	};
+	JOB_RUNNER.send(submission.clone(), user, priority);
 	return submission;
 }
 
+/// rejudge finished jobs, each against its given problem version
+pub fn rerun_batch(
+	jobs: Vec<(SubmissionRef, Arc<config::Problem>)>,
+	priority: Priority,
+) -> Arc<Batch> {
+	let batch = {
+		let mut batch_list = BATCH_LIST.lock().unwrap();
+		let batch = Arc::new(Batch {
+			id:           batch_list.len() as u64,
+			created_time: Utc::now(),
+			jobs:         jobs
+				.iter()
+				.map(|(job, _)| (job.clone(), job.lock().unwrap().result_final))
+				.collect(),
+		});
+		batch_list.push(batch.clone());
+		batch
+	};
+	for (job, problem) in jobs {
+		rerun_job(job, problem, priority);
+	}
+	return batch;
+}
+
 pub fn cancel_job(submission: SubmissionRef) -> Result<(), ()> {
-	let mut submission = submission.lock().unwrap();
-	return match submission.state {
+	let mut guard = submission.lock().unwrap();
+	return match guard.state {
 		SubmissionState::Queueing => {
-			submission.state = SubmissionState::Canceled;
+			guard.state = SubmissionState::Canceled;
+			JOB_RUNNER.queue.remove(&submission);
 			Ok(())
 		}
 		_ => Err(()),
 	};
 }
 
+/// number of jobs to be judged before this one, None if not queueing
+pub fn queue_position(submission: &SubmissionRef) -> Option<usize> {
+	JOB_RUNNER.queue.position(submission)
+}
+
+/// jobs waiting to be judged, bulk rejudges do not count as they yield to everything else
+pub fn queue_len() -> usize {
+	JOB_RUNNER.queue.len_before(Priority::Rejudge)
+}
+
 pub fn get_list() -> MutexGuard<'static, Vec<SubmissionRef>> {
 	SUBMISSION_LIST.lock().unwrap()
 }
+
+pub fn get_runs() -> MutexGuard<'static, Vec<SubmissionRef>> {
+	RUN_LIST.lock().unwrap()
+}
+
+pub fn get_stress_tests() -> MutexGuard<'static, Vec<SubmissionRef>> {
+	STRESS_LIST.lock().unwrap()
+}
+
+pub fn get_batches() -> MutexGuard<'static, Vec<Arc<Batch>>> {
+	BATCH_LIST.lock().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn queue() -> JobQueue<u64> {
+		JobQueue {
+			queues: Mutex::new(Default::default()),
+			ready:  Condvar::new(),
+		}
+	}
+
+	#[test]
+	fn fair_queue_takes_turns() {
+		let mut queue = FairQueue::default();
+		let jobs = (0..4).map(Arc::new).collect::<Vec<_>>();
+		queue.push(1, jobs[0].clone());
+		queue.push(1, jobs[1].clone());
+		queue.push(1, jobs[2].clone());
+		queue.push(2, jobs[3].clone());
+		assert_eq!(queue.len(), 4);
+		let order = std::iter::from_fn(|| queue.pop())
+			.map(|job| *job)
+			.collect::<Vec<_>>();
+		assert_eq!(order, vec![0, 3, 1, 2]);
+		assert_eq!(queue.len(), 0);
+	}
+
+	#[test]
+	fn fair_queue_position() {
+		let mut queue = FairQueue::default();
+		let jobs = (0..5).map(Arc::new).collect::<Vec<_>>();
+		queue.push(1, jobs[0].clone());
+		queue.push(1, jobs[1].clone());
+		queue.push(1, jobs[2].clone());
+		queue.push(2, jobs[3].clone());
+		queue.push(3, jobs[4].clone());
+		// served as 0, 3, 4, 1, 2
+		let positions = jobs
+			.iter()
+			.map(|job| queue.position(job))
+			.collect::<Vec<_>>();
+		assert_eq!(positions, vec![Some(0), Some(3), Some(4), Some(1), Some(2)]);
+		assert_eq!(queue.position(&Arc::new(0)), None);
+
+		// the turn of a user ends with its last job
+		assert!(queue.remove(&jobs[3]));
+		assert!(!queue.remove(&jobs[3]));
+		assert_eq!(queue.position(&jobs[4]), Some(1));
+		assert_eq!(queue.position(&jobs[2]), Some(3));
+	}
+
+	#[test]
+	fn job_queue_serves_priorities_in_order() {
+		let queue = queue();
+		let rejudge = Arc::new(0);
+		let normal = Arc::new(1);
+		let live = Arc::new(2);
+		queue.push(rejudge.clone(), 1, Priority::Rejudge);
+		queue.push(normal.clone(), 1, Priority::Normal);
+		queue.push(live.clone(), 2, Priority::ContestLive);
+		assert_eq!(queue.position(&live), Some(0));
+		assert_eq!(queue.position(&normal), Some(1));
+		assert_eq!(queue.position(&rejudge), Some(2));
+		assert_eq!(queue.len_before(Priority::Rejudge), 2);
+
+		assert!(queue.remove(&normal));
+		assert_eq!(queue.position(&rejudge), Some(1));
+		assert!(Arc::ptr_eq(&queue.pop(), &live));
+		assert!(Arc::ptr_eq(&queue.pop(), &rejudge));
+		assert_eq!(queue.len_before(Priority::Rejudge), 0);
+	}
+}
@@ -0,0 +1,118 @@
+/// content-addressed storage of test data, each file is named by its sha256
+use {
+	sha2::{Digest, Sha256},
+	std::{
+		fs,
+		io::{Error, Read, Result},
+		path::{Path, PathBuf},
+		sync::atomic::{AtomicU64, Ordering},
+	},
+};
+
+pub struct Store {
+	dir:     PathBuf,
+	/// distinguishes temporary files of concurrent writers
+	counter: AtomicU64,
+}
+
+/// a file as stored, name is the path inside an archive
+#[derive(serde::Serialize, Debug)]
+pub struct Entry {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	pub hash: String,
+	pub size: u64,
+}
+
+fn is_hash(hash: &str) -> bool {
+	hash.len() == 64 && hash.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+impl Store {
+	pub fn open(dir: &Path) -> Result<Self> {
+		fs::create_dir_all(dir)?;
+		return Ok(Self {
+			dir:     dir.to_path_buf(),
+			counter: AtomicU64::new(0),
+		});
+	}
+
+	pub fn dir(&self) -> &Path {
+		&self.dir
+	}
+
+	/// path of a stored file, None if the hash is ill or unknown
+	pub fn path(&self, hash: &str) -> Option<PathBuf> {
+		let path = self.dir.join(hash);
+		(is_hash(hash) && path.is_file()).then_some(path)
+	}
+
+	/// move a finished temporary file into place, identical content may already be there
+	fn commit(&self, temp: &Path, hash: &str) -> Result<()> {
+		match self.path(hash) {
+			Some(_) => fs::remove_file(temp),
+			None => fs::rename(temp, self.dir.join(hash)),
+		}
+	}
+
+	fn temp(&self) -> PathBuf {
+		self.dir.join(format!(
+			".tmp-{}-{}",
+			std::process::id(),
+			self.counter.fetch_add(1, Ordering::Relaxed)
+		))
+	}
+
+	pub fn put(&self, content: &[u8]) -> Result<Entry> {
+		let hash = format!("{:x}", Sha256::digest(content));
+		if self.path(&hash).is_none() {
+			let temp = self.temp();
+			fs::write(&temp, content)?;
+			self.commit(&temp, &hash)?;
+		}
+		return Ok(Entry {
+			name: None,
+			hash,
+			size: content.len() as u64,
+		});
+	}
+
+	/// hash a local file, it is only copied if not stored yet
+	pub fn put_file(&self, path: &Path) -> Result<Entry> {
+		let mut hasher = Sha256::new();
+		let size = std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+		let hash = format!("{:x}", hasher.finalize());
+		if self.path(&hash).is_none() {
+			let temp = self.temp();
+			fs::copy(path, &temp)?;
+			self.commit(&temp, &hash)?;
+		}
+		return Ok(Entry {
+			name: None,
+			hash,
+			size,
+		});
+	}
+
+	/// store every regular file of a tar archive
+	pub fn put_archive(&self, archive: &[u8]) -> Result<Vec<Entry>> {
+		let mut entries = Vec::new();
+		for file in tar::Archive::new(archive).entries()? {
+			let mut file = file?;
+			if !file.header().entry_type().is_file() {
+				continue;
+			}
+			let name = file.path()?.to_string_lossy().to_string();
+			let mut content = Vec::new();
+			file.read_to_end(&mut content)?;
+			entries.push(Entry {
+				name: Some(name),
+				..self.put(&content)?
+			});
+		}
+		if entries.is_empty() {
+			return Err(Error::other("archive contains no file"));
+		}
+		return Ok(entries);
+	}
+}
@@ -0,0 +1,39 @@
+/// teams of users, entering contests as a unit
+use {
+	lazy_static::lazy_static,
+	std::sync::{Arc, Mutex, MutexGuard},
+};
+
+pub type TeamRef = Arc<Team>;
+pub struct Team {
+	pub id:       u64,
+	pub name:     String,
+	/// members, distinct and not empty
+	pub user_ids: Vec<u64>,
+}
+
+lazy_static! {
+	static ref TEAM_LIST: Arc<Mutex<Vec<TeamRef>>> = Arc::new(Mutex::new(Vec::new()));
+}
+
+pub fn get_list() -> MutexGuard<'static, Vec<TeamRef>> {
+	TEAM_LIST.lock().unwrap()
+}
+
+pub fn get(id: u64) -> Option<TeamRef> {
+	get_list().get(id as usize).cloned()
+}
+
+pub fn new_team(name: String, user_ids: Vec<u64>) -> Result<TeamRef, String> {
+	let mut list = get_list();
+	if list.iter().any(|team| team.name == name) {
+		return Err(format!("Team name '{}' already exists.", name));
+	}
+	let team = Arc::new(Team {
+		id: list.len() as u64,
+		name,
+		user_ids,
+	});
+	list.push(team.clone());
+	return Ok(team);
+}
@@ -29,6 +29,11 @@ lazy_static! {
 	));
 }
 
+/// root administers everything, there are no other admins yet
+pub fn is_admin(id: u64) -> bool {
+	return id == USER_ROOT.id;
+}
+
 pub fn get_list_id() -> MutexGuard<'static, Vec<UserRef>> {
 	USER_LIST_ID.lock().unwrap()
 }
@@ -0,0 +1,171 @@
+/// judging of the solutions a problem ships with, before it is published
+use {
+	crate::{
+		api::jobs,
+		config::{Config, Problem},
+		judger, service,
+	},
+	serde::{Deserialize, Serialize},
+	std::{
+		io::{Error, Result},
+		sync::Arc,
+		time::Duration,
+	},
+};
+
+/// suggested time limits leave this factor of headroom over the reference solution
+const TIME_LIMIT_FACTOR: u64 = 2;
+/// us, suggested time limits are rounded up to it
+const TIME_LIMIT_STEP: u64 = 100_000;
+
+/// how the reference solution ran
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Verification {
+	/// us, on the slowest case
+	pub max_time:             u64,
+	/// us
+	pub suggested_time_limit: u64,
+}
+
+/// judge every solution of problem and wait for them, None without a reference solution
+///
+/// fails if the reference solution is not accepted or a wrong one is
+pub fn verify(problem: &Problem) -> Result<Option<Verification>> {
+	if problem.solution.is_none() && problem.wrong_solutions.is_empty() {
+		return Ok(None);
+	}
+	let problem = Arc::new(problem.clone());
+	let judge = |code: &judger::Code| {
+		service::new_check(service::Request {
+			source:     Arc::new(code.source.clone()),
+			language:   Some(Arc::new(code.language.clone())),
+			problem:    problem.clone(),
+			submission: Arc::new(jobs::Submission {
+				source_code: Arc::new(code.source.clone()),
+				language:    code.language.name.clone(),
+				user_id:     0,
+				contest_id:  0,
+				problem_id:  problem.id,
+				outputs:     None,
+			}),
+			mode:       service::Mode::Judge,
+			pretests:   false,
+		})
+	};
+	let reference = problem.solution.as_ref().map(judge);
+	let wrong = problem
+		.wrong_solutions
+		.iter()
+		.map(judge)
+		.collect::<Vec<_>>();
+	while reference.iter().chain(&wrong).any(|job| {
+		matches!(
+			job.lock().unwrap().state,
+			service::SubmissionState::Queueing | service::SubmissionState::Running
+		)
+	}) {
+		std::thread::sleep(Duration::from_millis(100));
+	}
+
+	let mut failures = Vec::new();
+	let mut verification = None;
+	if let Some(job) = reference {
+		let job = job.lock().unwrap();
+		match (&job.state, job.result_final) {
+			(service::SubmissionState::SystemError(err), _) => {
+				failures.push(format!("reference solution: {}", err));
+			}
+			(_, judger::Resultat::Accepted) => {
+				let max_time = job
+					.result_cases
+					.iter()
+					.filter_map(|case| match case {
+						judger::CaseResult::Finished(info) => Some(info.time),
+						_ => None,
+					})
+					.max()
+					.unwrap_or(0);
+				verification = Some(Verification {
+					max_time,
+					suggested_time_limit: (max_time * TIME_LIMIT_FACTOR)
+						.div_ceil(TIME_LIMIT_STEP)
+						.max(1) * TIME_LIMIT_STEP,
+				});
+			}
+			(_, result) => {
+				let case = job.result_cases.iter().position(|case| {
+					matches!(case, judger::CaseResult::Finished(info) if info.result != judger::Resultat::Accepted)
+				});
+				failures.push(match case {
+					Some(case) => format!(
+						"reference solution got {} on case {}",
+						describe(result),
+						case + 1
+					),
+					None => format!("reference solution got {}", describe(result)),
+				});
+			}
+		}
+	}
+	for (index, job) in wrong.iter().enumerate() {
+		let job = job.lock().unwrap();
+		match (&job.state, job.result_final) {
+			(service::SubmissionState::SystemError(err), _) => {
+				failures.push(format!("wrong solution {}: {}", index + 1, err));
+			}
+			(_, judger::Resultat::Accepted) => {
+				failures.push(format!("wrong solution {} got Accepted", index + 1));
+			}
+			_ => {}
+		}
+	}
+	if !failures.is_empty() {
+		return Err(Error::other(format!(
+			"solutions not as expected: {}",
+			failures.join("; ")
+		)));
+	}
+	return Ok(verification);
+}
+
+/// judge the solutions of a problem about to be published, recording the verification on it
+pub fn verified(problem: Problem) -> Result<Problem> {
+	if let Some(verification) = verify(&problem)? {
+		let _ = problem.verification.set(verification);
+	}
+	return Ok(problem);
+}
+
+/// verify the problems of the config file in the background, failing ones take no submissions
+pub fn start(config: Arc<Config>) {
+	let mut problems = config
+		.problems
+		.read()
+		.unwrap()
+		.values()
+		.cloned()
+		.collect::<Vec<_>>();
+	problems.sort_by_key(|problem| problem.id);
+	std::thread::spawn(move || {
+		for problem in problems {
+			match verify(&problem) {
+				Ok(Some(verification)) => {
+					let _ = problem.verification.set(verification);
+				}
+				Ok(None) => {}
+				Err(err) => {
+					log::error!("Problem {} failed verification: {}", problem.id, err);
+					let _ = problem.unverified.set(err.to_string());
+				}
+			}
+		}
+	});
+}
+
+/// name of a result as shown in responses
+fn describe(result: judger::Resultat) -> String {
+	serde_json::to_value(result)
+		.ok()
+		.and_then(|value| value.as_str().map(String::from))
+		.unwrap_or_default()
+}
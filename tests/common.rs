@@ -350,8 +350,9 @@ impl TestCase {
                 );
             }
 
-            // polling until job is finished
-            let poll_url = format!("{}/jobs/{}", &self.prefix, job_id);
+            // polling until job is finished, under the collection it was posted to
+            let collection = c.request.path.split('/').next().unwrap_or("jobs");
+            let poll_url = format!("{}/{}/{}", &self.prefix, collection, job_id);
             for _ in 0..c.poll_count {
                 std::thread::sleep(Duration::from_secs(1));
                 body = check_status_and_get_body(&poll_url.as_str(), reqwest::Method::GET);
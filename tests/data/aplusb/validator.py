@@ -0,0 +1,14 @@
+#!/usr/bin/env python3
+import sys
+
+lines = sys.stdin.read().split('\n')
+if len(lines) != 3 or lines[2] != '':
+    print('expected two lines')
+    sys.exit(1)
+for name, line in zip('ab', lines):
+    if not line.lstrip('-').isdigit() or str(int(line)) != line:
+        print(f'{name} is not an integer')
+        sys.exit(1)
+    if abs(int(line)) > 2 * 10**9:
+        print(f'{name} is out of range')
+        sys.exit(1)
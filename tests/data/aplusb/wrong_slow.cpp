@@ -0,0 +1,13 @@
+#include <iostream>
+
+int main() {
+	long long a, b;
+	std::cin >> a >> b;
+	// count up to the sum one by one
+	volatile long long sum = 0;
+	for (long long i = 0; i < (a + b) * 1000000; i++) {
+		sum = sum + 1;
+	}
+	std::cout << sum / 1000000 << std::endl;
+	return 0;
+}
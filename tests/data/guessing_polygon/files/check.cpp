@@ -0,0 +1,11 @@
+#include "testlib.h"
+#include <cmath>
+
+int main(int argc, char *argv[]) {
+    registerTestlibCmd(argc, argv);
+    double output = ouf.readDouble();
+    double answer = ans.readDouble();
+    if (std::fabs((output - answer) / answer) < 0.1)
+        quitf(_ok, "The error is within bounds");
+    quitf(_wa, "The error is beyond bounds");
+}
@@ -0,0 +1,224 @@
+mod common;
+use common::TestCase;
+
+#[test]
+fn test_ext_01_file_io() {
+    // check that named input/output files are supported
+    // 1. read input.txt and write output.txt
+    // 2. stdin/stdout solution gets no input and produces no output file
+    TestCase::read("ext_01_file_io").run();
+}
+
+#[test]
+fn test_ext_02_output_only() {
+    // check that output-only problems judge uploaded outputs without compiling
+    // 1. one correct and one wrong output, each case scored on its own
+    // 2. submitting source code instead of outputs is rejected
+    // 3. so are outputs not matching the cases in number
+    TestCase::read("ext_02_output_only").run();
+}
+
+#[test]
+fn test_ext_03_subtasks() {
+    // check subtask scoring policies and dependencies
+    // 1. all subtasks accepted
+    // 2. failed subtask skips the rest of its cases and blocks its dependents
+    TestCase::read("ext_03_subtasks").run();
+}
+
+#[test]
+fn test_ext_04_import() {
+    // check that a problem can be loaded from a directory package
+    // 1. cases are picked up as numbered .in/.ans pairs with even scores
+    TestCase::read("ext_04_import").run();
+}
+
+#[test]
+fn test_ext_05_problem_admin() {
+    // check that problems can be managed at runtime
+    // 1. create, reject duplicate or ill-formed ones, update to a new version
+    // 2. judge against the latest version, then delete
+    // 3. only admins manage problems, and only with stored data, not local files
+    TestCase::read("ext_05_problem_admin").run();
+}
+
+#[test]
+fn test_ext_06_data_store() {
+    // check that uploaded data can be used by hash
+    // 1. upload input and answer, create a problem referring to them
+    // 2. unknown hashes are rejected
+    // 3. only admins upload data
+    TestCase::read("ext_06_data_store").run();
+}
+
+#[test]
+fn test_ext_07_rejudge_version() {
+    // check that rejudge can pick the problem version
+    // 1. fix the answer of a problem after a job was judged
+    // 2. rejudge against the same, latest and a numbered version
+    TestCase::read("ext_07_rejudge_version").run();
+}
+
+#[test]
+fn test_ext_08_bulk_rejudge() {
+    // check that matching jobs are rejudged as one batch
+    // 1. fix a problem, rejudge its wrong answers against the latest version
+    // 2. batch progress reports the changed verdicts
+    // 3. only admins start rejudges
+    TestCase::read("ext_08_bulk_rejudge").run();
+}
+
+#[test]
+fn test_ext_09_rate_limit() {
+    // check that submissions are rate limited per user
+    // 1. a user exceeding the burst is refused with ERR_RATE_LIMIT
+    // 2. other users are not affected
+    TestCase::read("ext_09_rate_limit").run();
+}
+
+#[test]
+fn test_ext_10_custom_run() {
+    // check that code can be run on custom input without judging
+    // 1. a run reports the output of the program and its exit status
+    // 2. runs are not listed among jobs
+    TestCase::read("ext_10_custom_run").run();
+}
+
+#[test]
+fn test_ext_11_case_output() {
+    // check that failed cases keep their output for problems showing it
+    // 1. a wrong answer reports output, answer and the differing line
+    // 2. problems hiding outputs refuse the request
+    TestCase::read("ext_11_case_output").run();
+}
+
+#[test]
+fn test_ext_12_feedback() {
+    // check that job responses follow the feedback policy of the problem
+    // 1. verdict only, first failure only and none hide parts of the result
+    // 2. admins see full detail with the admin token only
+    // 3. hidden cases cannot be fetched, nor hidden results filtered on
+    // 4. the policy of a contest replaces that of its problems, hidden results show once it ended
+    // 5. ranklists hide the scores of others the policy hides until then
+    TestCase::read("ext_12_feedback").run();
+}
+
+#[test]
+fn test_ext_13_samples() {
+    // check that sample cases are public, judged first and may score nothing
+    // 1. sample data can be downloaded
+    // 2. a failed sample skips the hidden cases, and is shown in full
+    // 3. only hidden cases score
+    TestCase::read("ext_13_samples").run();
+}
+
+#[test]
+fn test_ext_14_pretests() {
+    // check that contests with pretests judge only the pretest cases while running
+    // 1. users outside the contest cannot submit
+    // 2. cases other than pretests are skipped and the ranklist shows pretest scores
+    // 3. subtasks without pretests hold back none of their dependents
+    TestCase::read("ext_14_pretests").run();
+}
+
+#[test]
+fn test_ext_15_hacking() {
+    // check that accepted submissions can be hacked once the contest ends
+    // 1. hacks are refused while running and on own submissions
+    // 2. invalid inputs are rejected by the validator without penalty
+    // 3. hacks are answered by the reference solution, and count in the ranklist
+    TestCase::read("ext_15_hacking").run();
+}
+
+#[test]
+fn test_ext_16_validator() {
+    // check that case inputs are validated when a problem is loaded
+    // 1. a problem with inputs violating the constraints is refused, naming the cases
+    // 2. a problem with valid inputs is created and judged as usual
+    // 3. uploads for a problem are refused unless its validator accepts them
+    TestCase::read("ext_16_validator").run();
+}
+
+#[test]
+fn test_ext_17_solutions() {
+    // check that the solutions of a problem are judged when it is loaded
+    // 1. the reference solution is accepted and suggests a time limit
+    // 2. loading fails if a wrong solution is accepted or the reference one is not
+    // 3. problems of the config file failing so are kept, but take no submissions
+    TestCase::read("ext_17_solutions").run();
+}
+
+#[test]
+fn test_ext_18_stress_tests() {
+    // check that stress tests compare code with a brute force on generated inputs
+    // 1. the first input the code fails on is reported with both outputs
+    // 2. code agreeing on every input is accepted
+    // 3. a generator failing to compile is reported as a compilation error
+    // 4. the brute force runs under its own time limit, not the problem's
+    TestCase::read("ext_18_stress_tests").run();
+}
+
+#[test]
+fn test_ext_19_icpc() {
+    // check the ICPC ranklist of a contest
+    // 1. users rank by problems solved then penalty, compilation errors do not count
+    // 2. tries after the freeze are pending, except for admins with the admin token
+    // 3. admins with the admin token reveal the ranklist once the contest ends
+    // 4. results of tries after the freeze are hidden from all but owners and admins until then
+    TestCase::read("ext_19_icpc").run();
+}
+
+#[test]
+fn test_ext_20_ioi() {
+    // check the IOI scoring rule of ranklists
+    // 1. subtask scores are kept on each submission
+    // 2. the best score of each subtask counts, even from different submissions
+    TestCase::read("ext_20_ioi").run();
+}
+
+#[test]
+fn test_ext_21_contest_problems() {
+    // check labeled problem entries of contests
+    // 1. entries get labels and names, and show on jobs of the contest
+    // 2. per-problem submission limits and time limits apply
+    // 3. ranklist scores are weighted
+    // 4. entries disagreeing with problem_ids or repeating labels are rejected
+    TestCase::read("ext_21_contest_problems").run();
+}
+
+#[test]
+fn test_ext_22_virtual() {
+    // check virtual participation of finished contests
+    // 1. only finished contests can be replayed, once per user and not from the past
+    // 2. a running participation lets the user submit to the contest
+    // 3. the ranklist leaves virtual submissions out, unless merging the participation
+    //    with the standings as of the same contest time
+    TestCase::read("ext_22_virtual").run();
+}
+
+#[test]
+fn test_ext_23_registration() {
+    // check registration of users and teams to contests
+    // 1. teams group existing users
+    // 2. registration takes a valid invitation code, or waits for an admin with the admin token
+    //    to approve it
+    // 3. a user enters a contest at most once, alone or with a team
+    // 4. teams share submission limits and are ranked as a unit, under contest display names
+    TestCase::read("ext_23_registration").run();
+}
+
+#[test]
+fn test_ext_24_limits() {
+    // check that limits fall back from case to subtask to problem
+    // 1. time limits of cases override those of subtasks, which override the problem's
+    // 2. a case stack limit fails deep recursion, which runs under the default stack
+    TestCase::read("ext_24_limits").run();
+}
+
+#[test]
+fn test_ext_25_package_checker() {
+    // check importing a polygon package with a custom checker
+    // 1. the configured special_judge stands in for the checker of the package
+    // 2. cases given in the config amend the imported ones
+    TestCase::read("ext_25_package_checker").run();
+}